@@ -0,0 +1,315 @@
+#![cfg(target_os = "linux")]
+
+//! Scheduler and virtual memory activity counters, from `/proc/stat` and
+//! `/proc/vmstat`.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::time::Instant;
+
+use super::Error;
+
+/// System-wide counters from `/proc/stat`. Counters are since boot.
+#[derive(Debug, Clone, Default)]
+pub struct SystemActivity {
+    pub context_switches: u64,
+    /// Interrupts serviced, including unnumbered architecture specific ones.
+    pub interrupts: u64,
+    pub softirqs: u64,
+    /// Processes and threads created.
+    pub processes_forked: u64,
+    /// Threads currently runnable.
+    pub procs_running: u64,
+    /// Threads currently blocked waiting for I/O.
+    pub procs_blocked: u64,
+}
+
+/// Counters from `/proc/vmstat`, since boot.
+///
+/// Counters the kernel doesn't report, such as `thp_*` without transparent huge page
+/// support, are 0. The reclaim counters sum the zone specific counters of kernels
+/// older than 4.8.
+#[derive(Debug, Clone, Default)]
+pub struct VmStat {
+    /// Page faults, minor and major.
+    pub pgfault: u64,
+    /// Page faults that required reading from disk.
+    pub pgmajfault: u64,
+    /// KB paged in from disk.
+    pub pgpgin: u64,
+    /// KB paged out to disk.
+    pub pgpgout: u64,
+    /// Pages swapped in.
+    pub pswpin: u64,
+    /// Pages swapped out.
+    pub pswpout: u64,
+    /// Pages scanned by kswapd, the background reclaim.
+    pub pgscan_kswapd: u64,
+    /// Pages scanned by direct reclaim, which stalls the allocating process.
+    pub pgscan_direct: u64,
+    /// Pages reclaimed by kswapd.
+    pub pgsteal_kswapd: u64,
+    /// Pages reclaimed by direct reclaim.
+    pub pgsteal_direct: u64,
+    /// Processes killed by the OOM killer.
+    pub oom_kill: u64,
+    /// Transparent huge pages allocated on page fault.
+    pub thp_fault_alloc: u64,
+    /// Page faults that fell back to small pages because no huge page was available.
+    pub thp_fault_fallback: u64,
+    /// Transparent huge pages assembled by khugepaged.
+    pub thp_collapse_alloc: u64,
+    /// Transparent huge pages split into small pages.
+    pub thp_split_page: u64,
+    /// All counters, by name.
+    pub raw: HashMap<String, u64>,
+}
+
+/// Rates between two samples, per second.
+#[derive(Debug)]
+pub struct ActivityRates {
+    pub context_switches: f64,
+    pub interrupts: f64,
+    pub softirqs: f64,
+    pub forks: f64,
+    /// Threads runnable at the time of the sample, not a rate.
+    pub procs_running: u64,
+    /// Threads blocked at the time of the sample, not a rate.
+    pub procs_blocked: u64,
+    pub page_faults: f64,
+    pub major_faults: f64,
+    /// Pages swapped in.
+    pub swap_in: f64,
+    /// Pages swapped out.
+    pub swap_out: f64,
+    /// Pages scanned by kswapd and direct reclaim.
+    pub pages_scanned: f64,
+    /// Pages reclaimed by kswapd and direct reclaim.
+    pub pages_stolen: f64,
+    /// Processes killed by the OOM killer between the samples, not a rate.
+    pub oom_kills: u64,
+    /// Rates of all `/proc/vmstat` counters, by name. Counters that can decrease,
+    /// such as `nr_free_pages`, are not meaningful as rates.
+    pub vmstat: HashMap<String, f64>,
+}
+
+/// Computes scheduler and VM activity rates between calls, like vmstat(8).
+#[derive(Debug)]
+pub struct ActivitySampler {
+    last: Instant,
+    activity: SystemActivity,
+    vmstat: VmStat,
+}
+
+/// Get the scheduler counters from `/proc/stat`.
+pub fn system_activity() -> Result<SystemActivity, Error> {
+    let mut s = String::new();
+    File::open("/proc/stat")?.read_to_string(&mut s)?;
+    Ok(parse_stat(&s))
+}
+
+/// Get the virtual memory counters from `/proc/vmstat`.
+pub fn vmstat() -> Result<VmStat, Error> {
+    let mut s = String::new();
+    File::open("/proc/vmstat")?.read_to_string(&mut s)?;
+    Ok(parse_vmstat(&s))
+}
+
+impl ActivitySampler {
+    pub fn new() -> Result<ActivitySampler, Error> {
+        Ok(ActivitySampler {
+            last: Instant::now(),
+            activity: system_activity()?,
+            vmstat: vmstat()?,
+        })
+    }
+
+    /// Rates since the previous sample, or since the sampler was created.
+    pub fn sample(&mut self) -> Result<ActivityRates, Error> {
+        let activity = system_activity()?;
+        let vmstat = vmstat()?;
+        let now = Instant::now();
+        let rates = rates(&self.activity, &self.vmstat, &activity, &vmstat,
+                          now.duration_since(self.last).as_secs_f64());
+        self.last = now;
+        self.activity = activity;
+        self.vmstat = vmstat;
+        Ok(rates)
+    }
+}
+
+fn rates(last_activity: &SystemActivity, last_vmstat: &VmStat,
+         activity: &SystemActivity, vmstat: &VmStat, secs: f64) -> ActivityRates {
+    let rate = |last: u64, now: u64| {
+        if secs > 0.0 { now.saturating_sub(last) as f64 / secs } else { 0.0 }
+    };
+    ActivityRates {
+        context_switches: rate(last_activity.context_switches, activity.context_switches),
+        interrupts: rate(last_activity.interrupts, activity.interrupts),
+        softirqs: rate(last_activity.softirqs, activity.softirqs),
+        forks: rate(last_activity.processes_forked, activity.processes_forked),
+        procs_running: activity.procs_running,
+        procs_blocked: activity.procs_blocked,
+        page_faults: rate(last_vmstat.pgfault, vmstat.pgfault),
+        major_faults: rate(last_vmstat.pgmajfault, vmstat.pgmajfault),
+        swap_in: rate(last_vmstat.pswpin, vmstat.pswpin),
+        swap_out: rate(last_vmstat.pswpout, vmstat.pswpout),
+        pages_scanned: rate(last_vmstat.pgscan_kswapd + last_vmstat.pgscan_direct,
+                            vmstat.pgscan_kswapd + vmstat.pgscan_direct),
+        pages_stolen: rate(last_vmstat.pgsteal_kswapd + last_vmstat.pgsteal_direct,
+                           vmstat.pgsteal_kswapd + vmstat.pgsteal_direct),
+        oom_kills: vmstat.oom_kill.saturating_sub(last_vmstat.oom_kill),
+        vmstat: vmstat.raw.iter()
+            .map(|(name, &now)| {
+                let last = last_vmstat.raw.get(name).copied().unwrap_or(0);
+                (name.clone(), rate(last, now))
+            })
+            .collect(),
+    }
+}
+
+fn parse_stat(s: &str) -> SystemActivity {
+    let mut activity = SystemActivity::default();
+    for line in s.lines() {
+        let mut words = line.split_whitespace();
+        let field = match words.next() {
+            Some("ctxt") => &mut activity.context_switches,
+            Some("intr") => &mut activity.interrupts,
+            Some("softirq") => &mut activity.softirqs,
+            Some("processes") => &mut activity.processes_forked,
+            Some("procs_running") => &mut activity.procs_running,
+            Some("procs_blocked") => &mut activity.procs_blocked,
+            _ => continue,
+        };
+        // "intr" and "softirq" are followed by per-source counts after the total.
+        *field = words.next().and_then(|v| v.parse::<u64>().ok()).unwrap_or(0);
+    }
+    activity
+}
+
+fn parse_vmstat(s: &str) -> VmStat {
+    let raw: HashMap<String, u64> = s.lines()
+        .filter_map(|line| {
+            let mut words = line.split_whitespace();
+            let name = words.next()?;
+            let value = words.next()?.parse::<u64>().ok()?;
+            Some((name.to_string(), value))
+        })
+        .collect();
+    let get = |name: &str| raw.get(name).copied().unwrap_or(0);
+    // Before 4.8 reclaim was counted per zone, as in "pgscan_kswapd_normal".
+    let sum = |prefix: &str| -> u64 {
+        raw.iter()
+            .filter(|&(name, _)| {
+                name == prefix || (name.starts_with(prefix)
+                    && ["_dma", "_dma32", "_normal", "_movable", "_high"]
+                        .contains(&&name[prefix.len()..]))
+            })
+            .map(|(_, &value)| value)
+            .sum()
+    };
+    VmStat {
+        pgfault: get("pgfault"),
+        pgmajfault: get("pgmajfault"),
+        pgpgin: get("pgpgin"),
+        pgpgout: get("pgpgout"),
+        pswpin: get("pswpin"),
+        pswpout: get("pswpout"),
+        pgscan_kswapd: sum("pgscan_kswapd"),
+        pgscan_direct: sum("pgscan_direct"),
+        pgsteal_kswapd: sum("pgsteal_kswapd"),
+        pgsteal_direct: sum("pgsteal_direct"),
+        oom_kill: get("oom_kill"),
+        thp_fault_alloc: get("thp_fault_alloc"),
+        thp_fault_fallback: get("thp_fault_fallback"),
+        thp_collapse_alloc: get("thp_collapse_alloc"),
+        thp_split_page: get("thp_split_page"),
+        raw,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_system_activity() {
+        let activity = system_activity().unwrap();
+        assert!(activity.context_switches > 0);
+        assert!(activity.processes_forked > 0);
+        println!("system_activity(): {:?}", activity);
+    }
+
+    #[test]
+    pub fn test_vmstat() {
+        let vmstat = vmstat().unwrap();
+        assert!(vmstat.pgfault > 0);
+        assert!(vmstat.raw.contains_key("nr_free_pages"));
+    }
+
+    #[test]
+    pub fn test_activity_sampler() {
+        let mut sampler = ActivitySampler::new().unwrap();
+        let rates = sampler.sample().unwrap();
+        assert!(rates.context_switches >= 0.0);
+        println!("ActivitySampler::sample(): {:?}", rates.context_switches);
+    }
+
+    #[test]
+    pub fn test_parse_stat() {
+        let s = "cpu  10 0 20 300 0 0 0 0 0 0\n\
+                 cpu0 10 0 20 300 0 0 0 0 0 0\n\
+                 intr 165973 0 0 12\n\
+                 ctxt 531146\n\
+                 btime 1792375751\n\
+                 processes 18468\n\
+                 procs_running 2\n\
+                 procs_blocked 1\n\
+                 softirq 93886 0 46646 3\n";
+        let activity = parse_stat(s);
+        assert_eq!(activity.interrupts, 165973);
+        assert_eq!(activity.context_switches, 531146);
+        assert_eq!(activity.processes_forked, 18468);
+        assert_eq!(activity.procs_running, 2);
+        assert_eq!(activity.procs_blocked, 1);
+        assert_eq!(activity.softirqs, 93886);
+    }
+
+    #[test]
+    pub fn test_parse_vmstat() {
+        let s = "nr_free_pages 1000\n\
+                 pgfault 5000\n\
+                 pgmajfault 7\n\
+                 pgscan_kswapd_dma32 10\n\
+                 pgscan_kswapd_normal 20\n\
+                 pgscan_direct 5\n\
+                 pgscan_direct_throttle 99\n\
+                 oom_kill 1\n";
+        let vmstat = parse_vmstat(s);
+        assert_eq!(vmstat.pgfault, 5000);
+        assert_eq!(vmstat.pgmajfault, 7);
+        assert_eq!(vmstat.pgscan_kswapd, 30);
+        assert_eq!(vmstat.pgscan_direct, 5);
+        assert_eq!(vmstat.oom_kill, 1);
+        assert_eq!(vmstat.thp_fault_alloc, 0);
+        assert_eq!(vmstat.raw["nr_free_pages"], 1000);
+    }
+
+    #[test]
+    pub fn test_rates() {
+        let last = SystemActivity { context_switches: 100, ..Default::default() };
+        let now = SystemActivity { context_switches: 300, procs_running: 3, ..Default::default() };
+        let last_vm = parse_vmstat("pgfault 10\npgmajfault 2\noom_kill 1\n");
+        let now_vm = parse_vmstat("pgfault 50\npgmajfault 2\noom_kill 3\npswpin 4\n");
+        let rates = rates(&last, &last_vm, &now, &now_vm, 2.0);
+        assert_eq!(rates.context_switches, 100.0);
+        assert_eq!(rates.procs_running, 3);
+        assert_eq!(rates.page_faults, 20.0);
+        assert_eq!(rates.major_faults, 0.0);
+        assert_eq!(rates.swap_in, 2.0);
+        assert_eq!(rates.oom_kills, 2);
+        assert_eq!(rates.vmstat["pgfault"], 20.0);
+        assert_eq!(rates.vmstat["pswpin"], 2.0);
+    }
+}
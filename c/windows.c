@@ -103,6 +103,14 @@ unsigned long get_proc_total(void) {
 	return cprocesses;
 }
 
+/*
+  get_uptime_ms
+  milliseconds since boot
+*/
+unsigned long long get_uptime_ms(void) {
+	return GetTickCount64();
+}
+
 MemInfo get_mem_info(void) {
 	MEMORYSTATUSEX stat;
 	/* DWORDLONG size; */
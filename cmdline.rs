@@ -0,0 +1,123 @@
+#![cfg(target_os = "linux")]
+
+//! Kernel command line, from `/proc/cmdline`.
+
+use std::fs::File;
+use std::io::Read;
+
+use super::Error;
+
+/// One parameter on the kernel command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KernelParam {
+    /// Such as "isolcpus" or "nosmt".
+    pub key: String,
+    /// The value after '=', without surrounding quotes. `None` for flags.
+    pub value: Option<String>,
+}
+
+/// The parameters the kernel was booted with, in order.
+#[derive(Debug, Clone, Default)]
+pub struct KernelCmdline {
+    pub params: Vec<KernelParam>,
+}
+
+impl KernelCmdline {
+    /// Parse a command line like `root=/dev/sda1 quiet acpi_osi="Windows 2020"`.
+    pub fn parse(s: &str) -> KernelCmdline {
+        let mut params = Vec::new();
+        let mut chars = s.trim().chars().peekable();
+        loop {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            if chars.peek().is_none() {
+                break;
+            }
+            // Quotes group spaces into one parameter and are dropped, as in the kernel.
+            let mut word = String::new();
+            let mut in_quotes = false;
+            let mut has_value = false;
+            let mut key_len = 0;
+            for c in chars.by_ref() {
+                match c {
+                    '"' => in_quotes = !in_quotes,
+                    c if c.is_whitespace() && !in_quotes => break,
+                    '=' if !has_value => {
+                        has_value = true;
+                        key_len = word.len();
+                        word.push(c);
+                    }
+                    c => word.push(c),
+                }
+            }
+            let param = if has_value {
+                KernelParam {
+                    key: word[..key_len].to_string(),
+                    value: Some(word[key_len + 1..].to_string()),
+                }
+            } else {
+                KernelParam { key: word, value: None }
+            };
+            params.push(param);
+        }
+        KernelCmdline { params }
+    }
+
+    /// Value of the last occurrence of a parameter, which is the one the kernel uses.
+    ///
+    /// Flags without a value return `Some("")`.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.params.iter()
+            .rev()
+            .find(|param| param.key == key)
+            .map(|param| param.value.as_deref().unwrap_or(""))
+    }
+
+    /// Values of all occurrences of a parameter, such as several `console=`.
+    pub fn get_all(&self, key: &str) -> Vec<&str> {
+        self.params.iter()
+            .filter(|param| param.key == key)
+            .map(|param| param.value.as_deref().unwrap_or(""))
+            .collect()
+    }
+
+    /// Whether a parameter is present, with or without a value.
+    pub fn has(&self, key: &str) -> bool {
+        self.params.iter().any(|param| param.key == key)
+    }
+}
+
+/// Get the kernel command line.
+pub fn kernel_cmdline() -> Result<KernelCmdline, Error> {
+    let mut s = String::new();
+    File::open("/proc/cmdline")?.read_to_string(&mut s)?;
+    Ok(KernelCmdline::parse(&s))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_kernel_cmdline() {
+        let cmdline = kernel_cmdline().unwrap();
+        println!("kernel_cmdline(): {:?}", cmdline);
+    }
+
+    #[test]
+    pub fn test_parse_kernel_cmdline() {
+        let cmdline = KernelCmdline::parse(
+            "BOOT_IMAGE=/vmlinuz root=UUID=1234 ro quiet isolcpus=2-3,6 nosmt \
+             acpi_osi=\"Windows 2020\" console=tty0 console=ttyS0,115200n8 \"dyndbg=file x.c +p\"\n");
+        assert_eq!(cmdline.get("root"), Some("UUID=1234"));
+        assert_eq!(cmdline.get("isolcpus"), Some("2-3,6"));
+        assert_eq!(cmdline.get("quiet"), Some(""));
+        assert!(cmdline.has("nosmt"));
+        assert!(!cmdline.has("nosmt=1"));
+        assert_eq!(cmdline.get("acpi_osi"), Some("Windows 2020"));
+        assert_eq!(cmdline.get("console"), Some("ttyS0,115200n8"));
+        assert_eq!(cmdline.get_all("console"), vec!["tty0", "ttyS0,115200n8"]);
+        assert_eq!(cmdline.get("dyndbg"), Some("file x.c +p"));
+        assert_eq!(cmdline.params[0].key, "BOOT_IMAGE");
+        assert_eq!(cmdline.params.len(), 10);
+    }
+}
@@ -0,0 +1,314 @@
+#![cfg(target_os = "linux")]
+
+//! Container runtime detection.
+
+use std::env;
+use std::path::Path;
+
+use super::{Error, Process, read_trimmed};
+
+/// A container runtime or sandbox.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContainerRuntime {
+    Docker,
+    Podman,
+    /// containerd without Kubernetes, for example through nerdctl.
+    Containerd,
+    /// A Kubernetes pod, whatever the runtime underneath.
+    Kubernetes,
+    Lxc,
+    SystemdNspawn,
+    /// Windows Subsystem for Linux.
+    Wsl,
+    Flatpak,
+    /// Another runtime announced through the `container` environment variable.
+    Other(String),
+}
+
+/// The container the calling process runs in.
+#[derive(Debug)]
+pub struct ContainerInfo {
+    pub runtime: ContainerRuntime,
+    /// Container ID, if it could be found in the cgroup paths or the files the
+    /// runtime bind mounts, such as `/etc/hostname`.
+    pub id: Option<String>,
+}
+
+/// Detect whether the calling process runs inside a container.
+///
+/// Returns `None` when no container is detected. Notice, inside a container
+/// functions like `mem_info()` and `cpu_num()` report the values of the host,
+/// not the limits of the container.
+pub fn container() -> Result<Option<ContainerInfo>, Error> {
+    let cgroup = read_trimmed("/proc/self/cgroup").unwrap_or_default();
+    let mountinfo = read_trimmed("/proc/self/mountinfo").unwrap_or_default();
+    let from_cgroup = container_from_cgroup(&cgroup);
+    let id = || {
+        from_cgroup.as_ref()
+            .and_then(|(_, id)| id.clone())
+            .or_else(|| id_from_mounts(&mountinfo))
+    };
+    let detected = |runtime: ContainerRuntime, id: Option<String>| {
+        Ok(Some(ContainerInfo { runtime, id }))
+    };
+
+    if Path::new("/.flatpak-info").exists() {
+        return detected(ContainerRuntime::Flatpak, None);
+    }
+    if env::var_os("KUBERNETES_SERVICE_HOST").is_some() || cgroup.contains("kubepods") {
+        return detected(ContainerRuntime::Kubernetes, id());
+    }
+    // systemd and most runtimes set $container for pid 1. Reading its environment
+    // needs privileges, so also check the file systemd copies it to.
+    let container_env = match read_trimmed("/run/systemd/container").unwrap_or_default().as_str() {
+        "" => pid1_container_env(),
+        value => Some(value.to_string()),
+    };
+    if let Some(value) = container_env {
+        let runtime = runtime_from_env(&value);
+        let id = if runtime == ContainerRuntime::Podman { podman_id().or_else(id) } else { id() };
+        return detected(runtime, id);
+    }
+    if Path::new("/run/.containerenv").exists() {
+        return detected(ContainerRuntime::Podman, podman_id().or_else(id));
+    }
+    if Path::new("/.dockerenv").exists() {
+        return detected(ContainerRuntime::Docker, id());
+    }
+    let osrelease = read_trimmed("/proc/sys/kernel/osrelease").unwrap_or_default();
+    Ok(detect_from_proc(&cgroup, &mountinfo, &osrelease))
+}
+
+/// Detect a container from the cgroups and mounts of the process, falling back to
+/// WSL by the kernel release.
+///
+/// WSL comes last: containers share the kernel of the host, so every container on
+/// a WSL2 host, such as those of Docker Desktop on Windows, sees its release.
+fn detect_from_proc(cgroup: &str, mountinfo: &str, osrelease: &str) -> Option<ContainerInfo> {
+    let from_cgroup = container_from_cgroup(cgroup);
+    let runtime = from_cgroup.as_ref()
+        .map(|(runtime, _)| runtime.clone())
+        .or_else(|| runtime_from_root_mount(mountinfo));
+    if let Some(runtime) = runtime {
+        let id = from_cgroup.and_then(|(_, id)| id).or_else(|| id_from_mounts(mountinfo));
+        return Some(ContainerInfo { runtime, id });
+    }
+    let osrelease = osrelease.to_lowercase();
+    if osrelease.contains("microsoft") || osrelease.contains("wsl") {
+        return Some(ContainerInfo { runtime: ContainerRuntime::Wsl, id: None });
+    }
+    None
+}
+
+fn pid1_container_env() -> Option<String> {
+    let environ = Process::new(1).and_then(|p| p.environ()).ok()?;
+    environ.into_iter()
+        .find(|(key, _)| key == "container")
+        .map(|(_, value)| value)
+}
+
+fn runtime_from_env(value: &str) -> ContainerRuntime {
+    match value {
+        "docker" => ContainerRuntime::Docker,
+        "podman" => ContainerRuntime::Podman,
+        "lxc" | "lxc-libvirt" => ContainerRuntime::Lxc,
+        "systemd-nspawn" => ContainerRuntime::SystemdNspawn,
+        "wsl" => ContainerRuntime::Wsl,
+        other => ContainerRuntime::Other(other.to_string()),
+    }
+}
+
+/// Find the container scope in the cgroup paths of the process, such as
+/// `/system.slice/docker-<id>.scope` or `/docker/<id>`, with the container ID.
+///
+/// Only container scopes count, not the services of the runtimes themselves, such
+/// as `/system.slice/docker.service` for dockerd.
+fn container_from_cgroup(cgroup: &str) -> Option<(ContainerRuntime, Option<String>)> {
+    for line in cgroup.lines() {
+        let path = match line.splitn(3, ':').nth(2) {
+            Some(path) => path,
+            None => continue,
+        };
+        let components: Vec<&str> = path.split('/').filter(|c| !c.is_empty()).collect();
+        for (i, &component) in components.iter().enumerate() {
+            let parent = if i > 0 { components[i - 1] } else { "" };
+            let id = |prefix: &str| scope_id(component, prefix).map(String::from);
+            if let Some(id) = id("docker-") {
+                return Some((ContainerRuntime::Docker, Some(id)));
+            }
+            if let Some(id) = id("libpod-") {
+                return Some((ContainerRuntime::Podman, Some(id)));
+            }
+            if let Some(id) = id("cri-containerd-").or_else(|| id("nerdctl-")) {
+                return Some((ContainerRuntime::Containerd, Some(id)));
+            }
+            if let Some(id) = id("crio-") {
+                return Some((ContainerRuntime::Kubernetes, Some(id)));
+            }
+            if is_container_id(component) {
+                // cgroupfs layouts: /docker/<id> and /kubepods/burstable/pod<uid>/<id>.
+                if parent == "docker" {
+                    return Some((ContainerRuntime::Docker, Some(component.to_string())));
+                }
+                if parent.starts_with("pod") {
+                    return Some((ContainerRuntime::Kubernetes, Some(component.to_string())));
+                }
+            }
+            // LXC 4 uses /lxc.payload.<name>, older versions /lxc/<name>.
+            if component.starts_with("lxc.payload.") || (parent == "lxc" && i == 1) {
+                return Some((ContainerRuntime::Lxc, None));
+            }
+            if parent == "machine.slice" {
+                if let Some(name) = component.strip_prefix("machine-") {
+                    // libvirt names its scopes machine-<driver>\x2d<id>\x2d<name>.scope.
+                    if name.starts_with("lxc\\x2d") {
+                        return Some((ContainerRuntime::Lxc, None));
+                    }
+                    let vm_drivers = ["qemu\\x2d", "kvm\\x2d", "libxl\\x2d", "ch\\x2d", "bhyve\\x2d"];
+                    if !vm_drivers.iter().any(|driver| name.starts_with(driver)) {
+                        return Some((ContainerRuntime::SystemdNspawn, None));
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+/// The ID in a scope like "docker-<id>.scope", or a cgroupfs directory like "libpod-<id>".
+fn scope_id<'a>(component: &'a str, prefix: &str) -> Option<&'a str> {
+    let id = component.strip_prefix(prefix)?;
+    let id = id.strip_suffix(".scope").unwrap_or(id);
+    if is_container_id(id) { Some(id) } else { None }
+}
+
+/// Whether `s` is a 64 character hex ID, as used by Docker, Podman and containerd.
+fn is_container_id(s: &str) -> bool {
+    s.len() == 64 && s.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Guess the runtime from an overlay filesystem mounted as root.
+fn runtime_from_root_mount(mountinfo: &str) -> Option<ContainerRuntime> {
+    let root = mountinfo.lines().find(|line| {
+        line.split_whitespace().nth(4) == Some("/") && line.contains(" - overlay ")
+    })?;
+    if root.contains("/var/lib/docker/") {
+        Some(ContainerRuntime::Docker)
+    } else if root.contains("/var/lib/containers/") {
+        Some(ContainerRuntime::Podman)
+    } else if root.contains("containerd") {
+        Some(ContainerRuntime::Containerd)
+    } else {
+        None
+    }
+}
+
+/// The container ID Podman writes to /run/.containerenv as `id="..."`.
+fn podman_id() -> Option<String> {
+    read_trimmed("/run/.containerenv").unwrap_or_default().lines()
+        .find(|line| line.starts_with("id="))
+        .map(|line| line["id=".len()..].trim_matches('"').to_string())
+        .filter(|id| !id.is_empty())
+}
+
+/// Find the container ID in the files the runtime bind mounts into the container,
+/// such as `/var/lib/docker/containers/<id>/hostname`.
+///
+/// On cgroup v2 with a cgroup namespace the cgroup path is just "/", so this is the
+/// only place the ID shows up. The overlay layer IDs of the root mount are skipped.
+fn id_from_mounts(mountinfo: &str) -> Option<String> {
+    mountinfo.lines()
+        .filter_map(|line| line.split_whitespace().nth(3))
+        .find_map(|root| {
+            let components: Vec<&str> = root.split('/').collect();
+            (1..components.len()).find_map(|i| {
+                // Docker: containers/<id>, Podman: overlay-containers/<id>,
+                // nerdctl: containers/<namespace>/<id>.
+                let in_containers = matches!(components[i - 1], "containers" | "overlay-containers")
+                    || (i > 1 && components[i - 2] == "containers");
+                if in_containers && is_container_id(components[i]) {
+                    Some(components[i].to_string())
+                } else {
+                    None
+                }
+            })
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_container() {
+        let container = container().unwrap();
+        println!("container(): {:?}", container);
+    }
+
+    #[test]
+    pub fn test_container_from_cgroup() {
+        let id = "3f4e5b0c9a8d7e6f5a4b3c2d1e0f9a8b7c6d5e4f3a2b1c0d9e8f7a6b5c4d3e2f";
+        let cases = [
+            (format!("0::/system.slice/docker-{}.scope\n", id), ContainerRuntime::Docker),
+            (format!("12:pids:/docker/{}\n", id), ContainerRuntime::Docker),
+            (format!("0::/machine.slice/libpod-{}.scope/container\n", id), ContainerRuntime::Podman),
+            (format!("0::/system.slice/cri-containerd-{}.scope\n", id), ContainerRuntime::Containerd),
+            (format!("4:memory:/kubepods/burstable/pod1a2b/{}\n", id), ContainerRuntime::Kubernetes),
+        ];
+        for (cgroup, runtime) in cases.iter() {
+            let (found, found_id) = container_from_cgroup(cgroup).unwrap();
+            assert_eq!(&found, runtime);
+            assert_eq!(found_id.as_deref(), Some(id));
+        }
+        assert_eq!(container_from_cgroup("0::/lxc.payload.web/init.scope\n").unwrap().0,
+                   ContainerRuntime::Lxc);
+        assert_eq!(container_from_cgroup("0::/machine.slice/machine-debian.scope/payload\n").unwrap().0,
+                   ContainerRuntime::SystemdNspawn);
+    }
+
+    #[test]
+    pub fn test_container_from_host_cgroup() {
+        // Processes of the host, including the runtimes themselves.
+        for cgroup in ["0::/init.scope\n",
+                       "0::/system.slice/docker.service\n",
+                       "0::/system.slice/containerd.service\n",
+                       "0::/system.slice/lxcfs.service\n",
+                       "0::/machine.slice/machine-qemu\\x2d1\\x2dvm.scope/libvirt/emulator\n",
+                       "0::/machine.slice/libpod-conmon-3f4e5b0c.scope\n",
+                       "0::/user.slice/user-1000.slice/session-2.scope\n"].iter() {
+            assert!(container_from_cgroup(cgroup).is_none(), "{}", cgroup);
+        }
+    }
+
+    #[test]
+    pub fn test_detect_from_proc() {
+        let id = "3f4e5b0c9a8d7e6f5a4b3c2d1e0f9a8b7c6d5e4f3a2b1c0d9e8f7a6b5c4d3e2f";
+        let wsl = "5.15.153.1-microsoft-standard-WSL2";
+        let info = detect_from_proc(&format!("0::/docker/{}\n", id), "", wsl).unwrap();
+        assert_eq!(info.runtime, ContainerRuntime::Docker);
+        assert_eq!(info.id.as_deref(), Some(id));
+        let info = detect_from_proc("0::/init.scope\n", "", wsl).unwrap();
+        assert_eq!(info.runtime, ContainerRuntime::Wsl);
+        assert!(detect_from_proc("0::/init.scope\n", "", "6.8.0-45-generic").is_none());
+    }
+
+    #[test]
+    pub fn test_runtime_from_root_mount() {
+        let id = "3f4e5b0c9a8d7e6f5a4b3c2d1e0f9a8b7c6d5e4f3a2b1c0d9e8f7a6b5c4d3e2f";
+        let layer = "9a8b7c6d5e4f3a2b1c0d9e8f7a6b5c4d3e2f3f4e5b0c9a8d7e6f5a4b3c2d1e0f";
+        let mountinfo = format!(
+            "1340 1200 0:120 / / rw,relatime master:1 - overlay overlay \
+             rw,lowerdir=/var/lib/docker/overlay2/l/ABC,upperdir=/var/lib/docker/overlay2/{layer}/diff\n\
+             1360 1340 254:1 /var/lib/docker/containers/{id}/resolv.conf /etc/resolv.conf rw - ext4 /dev/vda1 rw\n\
+             1361 1340 254:1 /var/lib/docker/containers/{id}/hostname /etc/hostname rw - ext4 /dev/vda1 rw\n",
+            layer = layer, id = id);
+        assert_eq!(runtime_from_root_mount(&mountinfo), Some(ContainerRuntime::Docker));
+        assert_eq!(id_from_mounts(&mountinfo).as_deref(), Some(id));
+        let podman = format!("800 700 0:50 /containers/storage/overlay-containers/{}/userdata/hostname \
+                              /etc/hostname rw - tmpfs tmpfs rw\n", id);
+        assert_eq!(id_from_mounts(&podman).as_deref(), Some(id));
+        let mountinfo = "28 1 254:0 / / rw,relatime - ext4 /dev/vda rw\n";
+        assert_eq!(runtime_from_root_mount(mountinfo), None);
+        assert_eq!(id_from_mounts(mountinfo), None);
+    }
+}
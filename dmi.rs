@@ -0,0 +1,74 @@
+#![cfg(target_os = "linux")]
+
+//! Hardware and firmware identity from DMI/SMBIOS, read from `/sys/class/dmi/id`.
+
+use std::path::Path;
+
+use super::{Error, read_trimmed};
+
+const DMI_ID: &str = "/sys/class/dmi/id";
+
+/// Hardware and firmware identity.
+///
+/// Fields the firmware doesn't provide are `None`, and so are the fields only root
+/// may read (`product_serial` and `product_uuid`) when called by another user. All
+/// fields are `None` on systems without DMI, such as most ARM boards.
+#[derive(Debug, Default)]
+pub struct DmiInfo {
+    /// Such as "Dell Inc." or "QEMU".
+    pub sys_vendor: Option<String>,
+    pub product_name: Option<String>,
+    pub product_version: Option<String>,
+    pub product_serial: Option<String>,
+    /// SMBIOS system UUID, stable across reinstalls of the operating system.
+    pub product_uuid: Option<String>,
+    pub board_vendor: Option<String>,
+    pub board_name: Option<String>,
+    pub bios_vendor: Option<String>,
+    pub bios_version: Option<String>,
+    /// Release date of the BIOS, usually as "MM/DD/YYYY".
+    pub bios_date: Option<String>,
+    /// SMBIOS chassis type, such as 3 for desktop, 10 for notebook or 23 for rack mount.
+    pub chassis_type: Option<u32>,
+}
+
+/// Get hardware and firmware identity from DMI.
+pub fn dmi_info() -> Result<DmiInfo, Error> {
+    let dir = Path::new(DMI_ID);
+    Ok(DmiInfo {
+        sys_vendor: read_field(dir, "sys_vendor"),
+        product_name: read_field(dir, "product_name"),
+        product_version: read_field(dir, "product_version"),
+        product_serial: read_field(dir, "product_serial"),
+        product_uuid: read_field(dir, "product_uuid"),
+        board_vendor: read_field(dir, "board_vendor"),
+        board_name: read_field(dir, "board_name"),
+        bios_vendor: read_field(dir, "bios_vendor"),
+        bios_version: read_field(dir, "bios_version"),
+        bios_date: read_field(dir, "bios_date"),
+        chassis_type: read_field(dir, "chassis_type").and_then(|t| t.parse::<u32>().ok()),
+    })
+}
+
+/// Read one DMI field, treating missing, unreadable and empty files as absent.
+fn read_field(dir: &Path, name: &str) -> Option<String> {
+    read_trimmed(dir.join(name)).ok().filter(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_dmi_info() {
+        let info = dmi_info().unwrap();
+        println!("dmi_info(): {:?}", info);
+    }
+
+    #[test]
+    pub fn test_read_field() {
+        let dir = Path::new("/proc/sys/kernel");
+        assert_eq!(read_field(dir, "ostype").unwrap(), "Linux");
+        assert!(read_field(dir, "no_such_field").is_none());
+    }
+}
@@ -0,0 +1,163 @@
+#![cfg(target_os = "linux")]
+
+//! Huge page pools and transparent huge page settings, from `/sys/kernel/mm`.
+
+use std::fs;
+use std::path::Path;
+
+use super::{Error, read_trimmed, selected};
+
+const HUGEPAGES: &str = "/sys/kernel/mm/hugepages";
+const THP: &str = "/sys/kernel/mm/transparent_hugepage";
+const NODES: &str = "/sys/devices/system/node";
+
+/// Huge pages of one size on one NUMA node.
+#[derive(Debug, Clone)]
+pub struct NodeHugePages {
+    pub node: u32,
+    pub nr: u64,
+    pub free: u64,
+    pub surplus: u64,
+}
+
+/// The pool of huge pages of one size. Counts are in pages.
+#[derive(Debug, Clone)]
+pub struct HugePagePool {
+    /// Page size in KB, such as 2048 or 1048576.
+    pub size: u64,
+    /// Pages in the pool, `vm.nr_hugepages` for the default size.
+    pub nr: u64,
+    pub free: u64,
+    /// Pages reserved for mappings but not yet faulted in.
+    pub reserved: u64,
+    /// Pages allocated beyond `nr` by overcommit.
+    pub surplus: u64,
+    /// Maximum number of surplus pages.
+    pub overcommit: u64,
+    /// The pool split by NUMA node. Empty on kernels without NUMA support.
+    pub nodes: Vec<NodeHugePages>,
+}
+
+/// Transparent huge page settings.
+#[derive(Debug, Clone)]
+pub struct TransparentHugePages {
+    /// "always", "madvise" or "never".
+    pub enabled: String,
+    /// "always", "defer", "defer+madvise", "madvise" or "never".
+    pub defrag: String,
+    /// Such as "never" or "within_size". `None` if not reported.
+    pub shmem_enabled: Option<String>,
+    /// Size of a transparent huge page in KB. `None` if not reported.
+    pub pmd_size: Option<u64>,
+}
+
+/// Huge page configuration.
+#[derive(Debug, Clone)]
+pub struct HugePages {
+    /// Pools by page size, smallest first. Empty without huge page support.
+    pub pools: Vec<HugePagePool>,
+    /// `None` if the kernel is built without transparent huge pages.
+    pub thp: Option<TransparentHugePages>,
+}
+
+/// Get the huge page pools and transparent huge page settings.
+pub fn hugepages() -> Result<HugePages, Error> {
+    let mut pools = Vec::new();
+    for (size, dir) in size_dirs(Path::new(HUGEPAGES)) {
+        let count = |name: &str| read_u64(&dir.join(name));
+        pools.push(HugePagePool {
+            size,
+            nr: count("nr_hugepages")?,
+            free: count("free_hugepages")?,
+            reserved: count("resv_hugepages")?,
+            surplus: count("surplus_hugepages")?,
+            overcommit: count("nr_overcommit_hugepages")?,
+            nodes: node_hugepages(size)?,
+        });
+    }
+
+    let thp_dir = Path::new(THP);
+    let thp = read_trimmed(thp_dir.join("enabled")).ok().map(|enabled| TransparentHugePages {
+        enabled: selected(&enabled),
+        defrag: read_trimmed(thp_dir.join("defrag")).map(|s| selected(&s)).unwrap_or_default(),
+        shmem_enabled: read_trimmed(thp_dir.join("shmem_enabled")).ok().map(|s| selected(&s)),
+        pmd_size: read_u64(&thp_dir.join("hpage_pmd_size")).ok().map(|bytes| bytes / 1024),
+    });
+    Ok(HugePages { pools, thp })
+}
+
+fn node_hugepages(size: u64) -> Result<Vec<NodeHugePages>, Error> {
+    let mut nodes = Vec::new();
+    let entries = match fs::read_dir(NODES) {
+        Ok(entries) => entries,
+        Err(_) => return Ok(nodes),
+    };
+    for entry in entries {
+        let entry = entry?;
+        let node = match entry.file_name().to_str()
+            .and_then(|name| name.strip_prefix("node"))
+            .and_then(|n| n.parse::<u32>().ok()) {
+            Some(node) => node,
+            None => continue,
+        };
+        let dir = entry.path().join(format!("hugepages/hugepages-{}kB", size));
+        if !dir.exists() {
+            continue;
+        }
+        nodes.push(NodeHugePages {
+            node,
+            nr: read_u64(&dir.join("nr_hugepages"))?,
+            free: read_u64(&dir.join("free_hugepages"))?,
+            surplus: read_u64(&dir.join("surplus_hugepages"))?,
+        });
+    }
+    nodes.sort_by_key(|node| node.node);
+    Ok(nodes)
+}
+
+/// Directories like "hugepages-2048kB" with their page size, smallest first.
+fn size_dirs(dir: &Path) -> Vec<(u64, std::path::PathBuf)> {
+    let mut dirs: Vec<_> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let size = parse_size_dir(entry.file_name().to_str()?)?;
+                Some((size, entry.path()))
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    dirs.sort();
+    dirs
+}
+
+fn parse_size_dir(name: &str) -> Option<u64> {
+    name.strip_prefix("hugepages-")?.strip_suffix("kB")?.parse::<u64>().ok()
+}
+
+fn read_u64(path: &Path) -> Result<u64, Error> {
+    read_trimmed(path).ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .ok_or_else(|| Error::General(format!("failed to read {}", path.display())))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_hugepages() {
+        let hugepages = hugepages().unwrap();
+        println!("hugepages(): {:?}", hugepages);
+        for pool in &hugepages.pools {
+            assert!(pool.free <= pool.nr + pool.surplus);
+        }
+    }
+
+    #[test]
+    pub fn test_parse_size_dir() {
+        assert_eq!(parse_size_dir("hugepages-2048kB"), Some(2048));
+        assert_eq!(parse_size_dir("hugepages-1048576kB"), Some(1048576));
+        assert_eq!(parse_size_dir("khugepaged"), None);
+    }
+}
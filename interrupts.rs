@@ -0,0 +1,310 @@
+#![cfg(target_os = "linux")]
+
+//! Interrupt and softirq counters, from `/proc/interrupts` and `/proc/softirqs`.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::time::Instant;
+
+use super::Error;
+
+/// One line of `/proc/interrupts`.
+#[derive(Debug, Clone)]
+pub struct Interrupt {
+    /// IRQ number such as "24", or a name such as "NMI" or "LOC".
+    pub irq: String,
+    /// Interrupts handled by each CPU, indexed by CPU number. Offline CPUs count 0.
+    ///
+    /// Lines counted system-wide, such as "ERR", have a single entry.
+    pub counts: Vec<u64>,
+    /// Interrupt controller, such as "IO-APIC" or "PCI-MSIX-0000:00:01.0".
+    pub chip: Option<String>,
+    /// Interrupt number on the controller and trigger type, such as "5-edge".
+    pub hw_irq: Option<String>,
+    /// Devices using the line, such as "eth0-TxRx-0".
+    pub devices: Vec<String>,
+    /// Description of named lines, such as "Local timer interrupts" for "LOC".
+    pub description: Option<String>,
+}
+
+/// One line of `/proc/softirqs`.
+#[derive(Debug, Clone)]
+pub struct SoftIrq {
+    /// Such as "NET_RX" or "TIMER".
+    pub name: String,
+    /// Softirqs handled by each CPU, indexed by CPU number. Offline CPUs count 0.
+    pub counts: Vec<u64>,
+}
+
+impl Interrupt {
+    /// Interrupts handled by all CPUs.
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+}
+
+impl SoftIrq {
+    /// Softirqs handled by all CPUs.
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+}
+
+/// Rate of an interrupt line or softirq between two samples.
+#[derive(Debug, Clone)]
+pub struct IrqRate {
+    /// IRQ number or name, as in `Interrupt::irq` and `SoftIrq::name`.
+    pub name: String,
+    /// Devices using the line, empty for softirqs.
+    pub devices: Vec<String>,
+    /// Per second, for each CPU, indexed by CPU number.
+    pub per_cpu: Vec<f64>,
+}
+
+impl IrqRate {
+    /// Per second, for all CPUs.
+    pub fn total(&self) -> f64 {
+        self.per_cpu.iter().sum()
+    }
+}
+
+/// Rates of all interrupt lines and softirqs between two samples.
+#[derive(Debug)]
+pub struct InterruptRates {
+    pub interrupts: Vec<IrqRate>,
+    pub softirqs: Vec<IrqRate>,
+}
+
+/// Computes interrupt and softirq rates between calls, to spot imbalance across CPUs.
+///
+/// Lines that appear between samples, such as when a driver is loaded, report zero
+/// rates in their first sample.
+#[derive(Debug)]
+pub struct InterruptSampler {
+    last: Instant,
+    interrupts: HashMap<String, Vec<u64>>,
+    softirqs: HashMap<String, Vec<u64>>,
+}
+
+/// Get the interrupt counters.
+pub fn interrupts() -> Result<Vec<Interrupt>, Error> {
+    let mut s = String::new();
+    File::open("/proc/interrupts")?.read_to_string(&mut s)?;
+    Ok(parse_interrupts(&s))
+}
+
+/// Get the softirq counters.
+pub fn softirqs() -> Result<Vec<SoftIrq>, Error> {
+    let mut s = String::new();
+    File::open("/proc/softirqs")?.read_to_string(&mut s)?;
+    Ok(parse_softirqs(&s))
+}
+
+impl InterruptSampler {
+    pub fn new() -> Result<InterruptSampler, Error> {
+        let mut sampler = InterruptSampler {
+            last: Instant::now(),
+            interrupts: HashMap::new(),
+            softirqs: HashMap::new(),
+        };
+        sampler.sample()?;
+        Ok(sampler)
+    }
+
+    /// Rates since the previous sample, or since the sampler was created.
+    pub fn sample(&mut self) -> Result<InterruptRates, Error> {
+        let interrupts = interrupts()?;
+        let softirqs = softirqs()?;
+        let now = Instant::now();
+        let secs = now.duration_since(self.last).as_secs_f64();
+        self.last = now;
+
+        let interrupt_rates = interrupts.into_iter()
+            .map(|irq| {
+                let per_cpu = rates(self.interrupts.get(&irq.irq), &irq.counts, secs);
+                self.interrupts.insert(irq.irq.clone(), irq.counts);
+                IrqRate { name: irq.irq, devices: irq.devices, per_cpu }
+            })
+            .collect();
+        let softirq_rates = softirqs.into_iter()
+            .map(|softirq| {
+                let per_cpu = rates(self.softirqs.get(&softirq.name), &softirq.counts, secs);
+                self.softirqs.insert(softirq.name.clone(), softirq.counts);
+                IrqRate { name: softirq.name, devices: Vec::new(), per_cpu }
+            })
+            .collect();
+        Ok(InterruptRates { interrupts: interrupt_rates, softirqs: softirq_rates })
+    }
+}
+
+fn rates(last: Option<&Vec<u64>>, counts: &[u64], secs: f64) -> Vec<f64> {
+    counts.iter()
+        .enumerate()
+        .map(|(cpu, &count)| match last.and_then(|last| last.get(cpu)) {
+            Some(&last) if secs > 0.0 => count.saturating_sub(last) as f64 / secs,
+            _ => 0.0,
+        })
+        .collect()
+}
+
+/// CPU numbers of the columns, from a header like "CPU0 CPU1 CPU3".
+fn parse_header(line: &str) -> Vec<usize> {
+    line.split_whitespace()
+        .filter_map(|word| word.strip_prefix("CPU")?.parse::<usize>().ok())
+        .collect()
+}
+
+/// Split a line into its name and the counts of each CPU column, returning the
+/// remaining words.
+fn parse_counts<'a>(line: &'a str, cpus: &[usize]) -> Option<(&'a str, Vec<u64>, Vec<&'a str>)> {
+    let mut parts = line.splitn(2, ':');
+    let name = parts.next()?.trim();
+    let mut words = parts.next()?.split_whitespace().peekable();
+    let mut columns = Vec::new();
+    while columns.len() < cpus.len() {
+        match words.next_if(|word| word.parse::<u64>().is_ok()) {
+            Some(word) => columns.push(word.parse::<u64>().ok()?),
+            None => break,
+        }
+    }
+    let counts = if columns.len() == cpus.len() {
+        let mut counts = vec![0; cpus.iter().max().map_or(0, |&max| max + 1)];
+        for (&cpu, count) in cpus.iter().zip(columns) {
+            counts[cpu] = count;
+        }
+        counts
+    } else {
+        columns
+    };
+    Some((name, counts, words.collect()))
+}
+
+fn parse_interrupts(s: &str) -> Vec<Interrupt> {
+    let mut lines = s.lines();
+    let cpus = match lines.next() {
+        Some(header) => parse_header(header),
+        None => return Vec::new(),
+    };
+    lines
+        .filter_map(|line| {
+            let (irq, counts, rest) = parse_counts(line, &cpus)?;
+            let mut interrupt = Interrupt {
+                irq: irq.to_string(),
+                counts,
+                chip: None,
+                hw_irq: None,
+                devices: Vec::new(),
+                description: None,
+            };
+            if irq.parse::<u32>().is_ok() {
+                let mut rest = rest.into_iter();
+                interrupt.chip = rest.next().map(String::from);
+                interrupt.hw_irq = rest.next().map(String::from);
+                // ARM controllers print the trigger type as a separate word.
+                let mut rest = rest.peekable();
+                if let Some(trigger) = rest.next_if(|&word| word == "Level" || word == "Edge") {
+                    interrupt.hw_irq = interrupt.hw_irq.map(|hw_irq| format!("{} {}", hw_irq, trigger));
+                }
+                interrupt.devices = rest.collect::<Vec<&str>>()
+                    .join(" ")
+                    .split(", ")
+                    .filter(|device| !device.is_empty())
+                    .map(String::from)
+                    .collect();
+            } else if !rest.is_empty() {
+                interrupt.description = Some(rest.join(" "));
+            }
+            Some(interrupt)
+        })
+        .collect()
+}
+
+fn parse_softirqs(s: &str) -> Vec<SoftIrq> {
+    let mut lines = s.lines();
+    let cpus = match lines.next() {
+        Some(header) => parse_header(header),
+        None => return Vec::new(),
+    };
+    lines
+        .filter_map(|line| {
+            let (name, counts, _) = parse_counts(line, &cpus)?;
+            Some(SoftIrq { name: name.to_string(), counts })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_interrupts() {
+        let interrupts = interrupts().unwrap();
+        assert!(!interrupts.is_empty());
+        println!("interrupts(): {:?}", interrupts);
+    }
+
+    #[test]
+    pub fn test_softirqs() {
+        let softirqs = softirqs().unwrap();
+        assert!(softirqs.iter().any(|softirq| softirq.name == "TIMER"));
+        println!("softirqs(): {:?}", softirqs);
+    }
+
+    #[test]
+    pub fn test_interrupt_sampler() {
+        let mut sampler = InterruptSampler::new().unwrap();
+        let rates = sampler.sample().unwrap();
+        assert!(!rates.softirqs.is_empty());
+        assert!(rates.interrupts.iter().all(|rate| rate.total() >= 0.0));
+    }
+
+    #[test]
+    pub fn test_parse_interrupts() {
+        let s = "           CPU0       CPU1       CPU3\n\
+                 \x20 0:         44          0          0   IO-APIC   2-edge      timer\n\
+                 \x2035:       1200        300          0   PCI-MSI 524288-edge      eth0-TxRx-0, eth0-TxRx-1\n\
+                 \x2011:         10          2          1     GICv3  30 Level     arch_timer\n\
+                 \x2040:          0          0          0   PCI-MSI 1048576-edge\n\
+                 NMI:          1          2          3   Non-maskable interrupts\n\
+                 ERR:          7\n";
+        let interrupts = parse_interrupts(s);
+        assert_eq!(interrupts.len(), 6);
+        assert_eq!(interrupts[0].irq, "0");
+        assert_eq!(interrupts[0].counts, vec![44, 0, 0, 0]);
+        assert_eq!(interrupts[0].chip.as_deref(), Some("IO-APIC"));
+        assert_eq!(interrupts[0].hw_irq.as_deref(), Some("2-edge"));
+        assert_eq!(interrupts[0].devices, vec!["timer"]);
+        assert_eq!(interrupts[1].counts, vec![1200, 300, 0, 0]);
+        assert_eq!(interrupts[1].total(), 1500);
+        assert_eq!(interrupts[1].devices, vec!["eth0-TxRx-0", "eth0-TxRx-1"]);
+        assert_eq!(interrupts[2].hw_irq.as_deref(), Some("30 Level"));
+        assert_eq!(interrupts[2].devices, vec!["arch_timer"]);
+        assert_eq!(interrupts[2].counts, vec![10, 2, 0, 1]);
+        assert!(interrupts[3].devices.is_empty());
+        assert_eq!(interrupts[4].irq, "NMI");
+        assert_eq!(interrupts[4].chip, None);
+        assert_eq!(interrupts[4].description.as_deref(), Some("Non-maskable interrupts"));
+        assert_eq!(interrupts[5].counts, vec![7]);
+    }
+
+    #[test]
+    pub fn test_parse_softirqs() {
+        let s = "                    CPU0       CPU1\n\
+                 \x20         HI:          0          1\n\
+                 \x20     NET_RX:       2904        100\n";
+        let softirqs = parse_softirqs(s);
+        assert_eq!(softirqs.len(), 2);
+        assert_eq!(softirqs[1].name, "NET_RX");
+        assert_eq!(softirqs[1].counts, vec![2904, 100]);
+        assert_eq!(softirqs[1].total(), 3004);
+    }
+
+    #[test]
+    pub fn test_rates() {
+        let last = vec![100, 200];
+        assert_eq!(rates(Some(&last), &[300, 200, 50], 2.0), vec![100.0, 0.0, 0.0]);
+        assert_eq!(rates(None, &[300], 2.0), vec![0.0]);
+    }
+}
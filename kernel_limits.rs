@@ -0,0 +1,139 @@
+#![cfg(target_os = "linux")]
+
+//! Usage of system-wide kernel tables against their limits, from `/proc/sys`.
+
+use std::fs;
+use std::io;
+
+use super::{Error, sysctl};
+use super::process::pids;
+
+/// Usage and limits of kernel tables. See proc(5) for the parameters.
+///
+/// The tables and limits are those of the whole system, but `processes` and `threads`
+/// only count what is visible in the PID namespace of the caller, so inside a container
+/// they are not comparable to `pid_max` and `threads_max`.
+#[derive(Debug)]
+pub struct KernelLimits {
+    /// Allocated file handles, from `fs.file-nr`.
+    pub files_allocated: u64,
+    /// Allocated but unused file handles. Always 0 on kernels since 2.6.
+    pub files_unused: u64,
+    /// Maximum number of file handles, `fs.file-max`.
+    pub files_max: u64,
+    /// Allocated inodes, from `fs.inode-nr`.
+    pub inodes_allocated: u64,
+    pub inodes_free: u64,
+    /// Number of processes in the PID namespace of the caller.
+    pub processes: u64,
+    /// Number of threads of those processes, counting each single-threaded process as one.
+    pub threads: u64,
+    /// Highest process ID plus one, `kernel.pid_max`.
+    pub pid_max: u64,
+    /// Maximum number of threads, `kernel.threads-max`.
+    pub threads_max: u64,
+    /// Outstanding asynchronous I/O requests, `fs.aio-nr`. `None` on kernels built
+    /// without AIO.
+    pub aio_nr: Option<u64>,
+    /// `fs.aio-max-nr`.
+    pub aio_max_nr: Option<u64>,
+    /// Per-user limit of inotify instances, `fs.inotify.max_user_instances`. `None` on
+    /// kernels built without inotify, as are the other inotify limits.
+    pub inotify_max_user_instances: Option<u64>,
+    /// Per-user limit of inotify watches, `fs.inotify.max_user_watches`.
+    pub inotify_max_user_watches: Option<u64>,
+    /// `fs.inotify.max_queued_events`.
+    pub inotify_max_queued_events: Option<u64>,
+}
+
+/// Get the usage of kernel tables, such as file handles and process IDs, together
+/// with their limits.
+pub fn kernel_limits() -> Result<KernelLimits, Error> {
+    let file_nr = read_u64s("fs.file-nr", 3)?;
+    let inode_nr = read_u64s("fs.inode-nr", 2)?;
+    let pids = pids()?;
+    Ok(KernelLimits {
+        files_allocated: file_nr[0],
+        files_unused: file_nr[1],
+        files_max: file_nr[2],
+        inodes_allocated: inode_nr[0],
+        inodes_free: inode_nr[1],
+        processes: pids.len() as u64,
+        threads: count_threads(&pids),
+        pid_max: read_u64("kernel.pid_max")?,
+        threads_max: read_u64("kernel.threads-max")?,
+        aio_nr: read_optional_u64("fs.aio-nr")?,
+        aio_max_nr: read_optional_u64("fs.aio-max-nr")?,
+        inotify_max_user_instances: read_optional_u64("fs.inotify.max_user_instances")?,
+        inotify_max_user_watches: read_optional_u64("fs.inotify.max_user_watches")?,
+        inotify_max_queued_events: read_optional_u64("fs.inotify.max_queued_events")?,
+    })
+}
+
+/// Count the threads of `pids`, skipping processes that have exited meanwhile.
+fn count_threads(pids: &[u32]) -> u64 {
+    pids.iter()
+        .filter_map(|pid| fs::read_dir(format!("/proc/{}/task", pid)).ok())
+        .map(|tasks| tasks.count() as u64)
+        .sum()
+}
+
+/// Read a parameter that only exists if the kernel is built with the feature.
+fn read_optional_u64(name: &str) -> Result<Option<u64>, Error> {
+    match read_u64(name) {
+        Ok(value) => Ok(Some(value)),
+        Err(Error::IO(ref e)) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn read_u64(name: &str) -> Result<u64, Error> {
+    Ok(read_u64s(name, 1)?[0])
+}
+
+/// Read a parameter holding at least `count` non-negative integers.
+fn read_u64s(name: &str, count: usize) -> Result<Vec<u64>, Error> {
+    let value = sysctl::read(name)?;
+    parse_u64s(&value, count)
+        .ok_or_else(|| Error::General(format!("unexpected value of {}: {}", name, value)))
+}
+
+fn parse_u64s(value: &str, count: usize) -> Option<Vec<u64>> {
+    let values = value.split_whitespace()
+        .map(|word| word.parse::<u64>().ok())
+        .collect::<Option<Vec<u64>>>()?;
+    if values.len() < count {
+        return None;
+    }
+    Some(values)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_kernel_limits() {
+        let limits = kernel_limits().unwrap();
+        println!("kernel_limits(): {:?}", limits);
+        assert!(limits.files_allocated <= limits.files_max);
+        assert!(limits.processes > 0);
+        assert!(limits.threads > 0);
+        assert!(limits.pid_max > 0);
+    }
+
+    #[test]
+    pub fn test_read_optional_u64() {
+        assert!(read_optional_u64("kernel.pid_max").unwrap().is_some());
+        assert_eq!(read_optional_u64("fs.no_such_table").unwrap(), None);
+        assert!(read_optional_u64("kernel.ostype").is_err());
+    }
+
+    #[test]
+    pub fn test_parse_u64s() {
+        assert_eq!(parse_u64s("9216\t0\t9223372036854775807", 3),
+                   Some(vec![9216, 0, 9223372036854775807]));
+        assert_eq!(parse_u64s("8706 0", 3), None);
+        assert_eq!(parse_u64s("abc", 1), None);
+    }
+}
@@ -0,0 +1,136 @@
+#![cfg(target_os = "linux")]
+
+//! Loaded kernel modules, from `/proc/modules` and `/sys/module`.
+
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::Path;
+
+use super::{Error, read_trimmed};
+
+/// A parameter of a kernel module, from `/sys/module/[name]/parameters`.
+#[derive(Debug)]
+pub struct ModuleParam {
+    pub name: String,
+    /// `None` if the parameter is not readable by the calling user.
+    pub value: Option<String>,
+}
+
+/// A loaded kernel module.
+#[derive(Debug)]
+pub struct KernelModule {
+    /// Such as "overlay" or "nvidia".
+    pub name: String,
+    /// Memory used by the module, in bytes.
+    pub size: u64,
+    /// Number of references. `None` if the kernel can't unload modules.
+    pub refcount: Option<u32>,
+    /// Modules depending on this one.
+    pub used_by: Vec<String>,
+    /// "Live", "Loading" or "Unloading".
+    pub state: String,
+    /// Load address. `None` when hidden from unprivileged users.
+    pub address: Option<u64>,
+    /// Taint flags, such as "O" for out-of-tree or "P" for proprietary modules.
+    pub taints: Option<String>,
+    /// Version declared by the module, if any.
+    pub version: Option<String>,
+    pub parameters: Vec<ModuleParam>,
+}
+
+/// Get the loaded kernel modules.
+///
+/// Returns an empty list on kernels built without module support. Modules built
+/// into the kernel are not listed.
+pub fn kernel_modules() -> Result<Vec<KernelModule>, Error> {
+    let mut s = String::new();
+    match File::open("/proc/modules") {
+        Ok(mut f) => f.read_to_string(&mut s)?,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(Error::IO(e)),
+    };
+    let mut modules = parse_modules(&s);
+    for module in &mut modules {
+        let dir = Path::new("/sys/module").join(&module.name);
+        module.version = read_trimmed(dir.join("version")).ok();
+        module.parameters = read_parameters(&dir.join("parameters"));
+    }
+    Ok(modules)
+}
+
+fn parse_modules(s: &str) -> Vec<KernelModule> {
+    s.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 5 {
+                return None;
+            }
+            let used_by = fields[3]
+                .split(',')
+                .filter(|name| !name.is_empty() && *name != "-")
+                .map(String::from)
+                .collect();
+            let address = fields.get(5)
+                .and_then(|addr| u64::from_str_radix(addr.trim_start_matches("0x"), 16).ok())
+                .filter(|&addr| addr != 0);
+            let taints = fields.get(6)
+                .map(|taints| taints.trim_matches(|c| c == '(' || c == ')').to_string());
+            Some(KernelModule {
+                name: fields[0].to_string(),
+                size: fields[1].parse::<u64>().ok()?,
+                refcount: fields[2].parse::<u32>().ok(),
+                used_by,
+                state: fields[4].to_string(),
+                address,
+                taints,
+                version: None,
+                parameters: Vec::new(),
+            })
+        })
+        .collect()
+}
+
+fn read_parameters(dir: &Path) -> Vec<ModuleParam> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    let mut parameters: Vec<ModuleParam> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| ModuleParam {
+            name: entry.file_name().to_string_lossy().into_owned(),
+            value: read_trimmed(entry.path()).ok(),
+        })
+        .collect();
+    parameters.sort_by(|a, b| a.name.cmp(&b.name));
+    parameters
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_kernel_modules() {
+        let modules = kernel_modules().unwrap();
+        println!("kernel_modules(): {} modules", modules.len());
+    }
+
+    #[test]
+    pub fn test_parse_modules() {
+        let s = "nf_nat 57344 2 nft_chain_nat,xt_MASQUERADE, Live 0xffffffffc0a5e000\n\
+                 overlay 151552 0 - Live 0x0000000000000000\n\
+                 nvidia 56344576 1520 - Live 0xffffffffc1000000 (POE)\n";
+        let modules = parse_modules(s);
+        assert_eq!(modules.len(), 3);
+        assert_eq!(modules[0].name, "nf_nat");
+        assert_eq!(modules[0].size, 57344);
+        assert_eq!(modules[0].refcount, Some(2));
+        assert_eq!(modules[0].used_by, vec!["nft_chain_nat", "xt_MASQUERADE"]);
+        assert_eq!(modules[0].address, Some(0xffffffffc0a5e000));
+        assert!(modules[1].used_by.is_empty());
+        assert_eq!(modules[1].address, None);
+        assert_eq!(modules[2].taints.as_ref().unwrap(), "POE");
+        assert_eq!(modules[2].state, "Live");
+    }
+}
@@ -7,6 +7,7 @@
 
 extern crate libc;
 
+use std::cmp::Ordering;
 use std::ffi;
 use std::fmt;
 use std::io::{self, Read};
@@ -24,13 +25,87 @@ use std::mem::size_of_val;
 use std::ptr::null_mut;
 #[cfg(not(target_os = "windows"))]
 use libc::timeval;
-#[cfg(any(target_os = "solaris", target_os = "illumos"))]
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 #[cfg(target_os = "linux")]
 use std::collections::HashMap;
 
 #[cfg(any(target_os = "solaris", target_os = "illumos"))]
 mod kstat;
+#[cfg(target_os = "linux")]
+mod process;
+#[cfg(target_os = "linux")]
+mod process_tree;
+#[cfg(target_os = "linux")]
+mod process_query;
+#[cfg(target_os = "linux")]
+mod dmi;
+#[cfg(target_os = "linux")]
+mod virt;
+#[cfg(target_os = "linux")]
+mod container;
+#[cfg(target_os = "linux")]
+mod machine_id;
+#[cfg(target_os = "linux")]
+mod users;
+#[cfg(target_os = "linux")]
+mod cmdline;
+#[cfg(target_os = "linux")]
+mod kmod;
+#[cfg(target_os = "linux")]
+mod sensors;
+#[cfg(target_os = "linux")]
+mod power;
+#[cfg(target_os = "linux")]
+mod kernel_limits;
+#[cfg(target_os = "linux")]
+mod interrupts;
+#[cfg(target_os = "linux")]
+mod activity;
+#[cfg(target_os = "linux")]
+mod swap;
+#[cfg(target_os = "linux")]
+mod hugepages;
+#[cfg(any(target_os = "linux", target_vendor = "apple", target_os = "freebsd", target_os = "netbsd"))]
+pub mod sysctl;
+
+#[cfg(target_os = "linux")]
+pub use process::{Process, FdInfo, LimitValue, ResourceLimit, ProcessIo, CgroupEntry, Namespace,
+                  Capabilities, ProcessInfo, processes, ProcessSampler, ProcessUsage,
+                  CpuNormalization, RUsage, SelfUsage, self_usage, MapUsage, MemoryMap, MapKind,
+                  MapSummary, summarize_memory_maps, SchedPolicy, ThreadInfo, resource_limits};
+#[cfg(target_os = "linux")]
+pub use process_tree::ProcessTree;
+#[cfg(target_os = "linux")]
+pub use process_query::ProcessQuery;
+#[cfg(target_os = "linux")]
+pub use dmi::{DmiInfo, dmi_info};
+#[cfg(target_os = "linux")]
+pub use virt::{Virtualization, virtualization};
+#[cfg(target_os = "linux")]
+pub use container::{ContainerRuntime, ContainerInfo, container};
+#[cfg(target_os = "linux")]
+pub use machine_id::{machine_id, machine_id_app_specific};
+#[cfg(target_os = "linux")]
+pub use users::{Session, LoginRecord, users, last_logins};
+#[cfg(target_os = "linux")]
+pub use cmdline::{KernelParam, KernelCmdline, kernel_cmdline};
+#[cfg(target_os = "linux")]
+pub use kmod::{ModuleParam, KernelModule, kernel_modules};
+#[cfg(target_os = "linux")]
+pub use sensors::{SensorKind, SensorReading, sensors};
+#[cfg(target_os = "linux")]
+pub use power::{PowerSupplyKind, PowerSupplyStatus, PowerSupply, power_supplies};
+#[cfg(target_os = "linux")]
+pub use kernel_limits::{KernelLimits, kernel_limits};
+#[cfg(target_os = "linux")]
+pub use interrupts::{Interrupt, SoftIrq, IrqRate, InterruptRates, InterruptSampler, interrupts,
+                     softirqs};
+#[cfg(target_os = "linux")]
+pub use activity::{SystemActivity, VmStat, ActivityRates, ActivitySampler, system_activity, vmstat};
+#[cfg(target_os = "linux")]
+pub use swap::{SwapKind, ZramStats, SwapDevice, ZswapInfo, swap_devices, zswap};
+#[cfg(target_os = "linux")]
+pub use hugepages::{NodeHugePages, HugePagePool, TransparentHugePages, HugePages, hugepages};
 
 #[cfg(any(target_vendor = "apple", target_os="freebsd", target_os = "openbsd", target_os = "netbsd"))]
 static OS_CTL_KERN: libc::c_int = 1;
@@ -281,6 +356,102 @@ pub struct DiskInfo {
     pub free: u64,
 }
 
+/// Kernel version, parsed from uname(2).
+///
+/// Comparisons only look at `major`, `minor` and `patch`, so that
+/// `kernel_version()? >= KernelVersion::new(5, 8, 0)` works as expected.
+#[derive(Debug, Clone, Default)]
+pub struct KernelVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+    /// Whatever follows the version numbers in the release, such as "-91-generic".
+    pub extra: String,
+
+    /// Such as "Linux" or "Darwin".
+    pub sysname: String,
+    /// Network node name, usually the hostname.
+    pub nodename: String,
+    /// Such as "5.15.0-91-generic".
+    pub release: String,
+    /// Such as "#101-Ubuntu SMP Tue Nov 14 13:30:08 UTC 2023".
+    pub version: String,
+    /// Such as "x86_64" or "aarch64".
+    pub machine: String,
+}
+
+impl KernelVersion {
+    /// Create a version to compare against, with empty uname fields.
+    pub fn new(major: u32, minor: u32, patch: u32) -> KernelVersion {
+        KernelVersion {
+            major,
+            minor,
+            patch,
+            ..Default::default()
+        }
+    }
+
+    /// Parse a release string like "5.15.0-91-generic".
+    ///
+    /// Missing minor and patch numbers are taken as 0.
+    pub fn parse(release: &str) -> Option<KernelVersion> {
+        let mut numbers = [0u32; 3];
+        let mut rest = release;
+        for (i, number) in numbers.iter_mut().enumerate() {
+            if i > 0 {
+                if !rest.starts_with('.') || !rest[1..].starts_with(|c: char| c.is_ascii_digit()) {
+                    break;
+                }
+                rest = &rest[1..];
+            }
+            let len = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+            if len == 0 {
+                return None;
+            }
+            *number = rest[..len].parse().ok()?;
+            rest = &rest[len..];
+        }
+        Some(KernelVersion {
+            major: numbers[0],
+            minor: numbers[1],
+            patch: numbers[2],
+            extra: rest.to_string(),
+            release: release.to_string(),
+            ..Default::default()
+        })
+    }
+
+    fn numbers(&self) -> (u32, u32, u32) {
+        (self.major, self.minor, self.patch)
+    }
+}
+
+impl PartialEq for KernelVersion {
+    fn eq(&self, other: &KernelVersion) -> bool {
+        self.numbers() == other.numbers()
+    }
+}
+
+impl Eq for KernelVersion {}
+
+impl PartialOrd for KernelVersion {
+    fn partial_cmp(&self, other: &KernelVersion) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for KernelVersion {
+    fn cmp(&self, other: &KernelVersion) -> Ordering {
+        self.numbers().cmp(&other.numbers())
+    }
+}
+
+impl fmt::Display for KernelVersion {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{}.{}.{}{}", self.major, self.minor, self.patch, self.extra)
+    }
+}
+
 /// Error types
 #[derive(Debug)]
 pub enum Error {
@@ -289,6 +460,10 @@ pub enum Error {
     IO(io::Error),
     SystemTime(std::time::SystemTimeError),
     General(String),
+    /// The process does not exist, or has exited.
+    ProcessNotFound(u32),
+    /// The calling user is not allowed to read the information.
+    PermissionDenied(io::Error),
     Unknown,
 }
 
@@ -301,6 +476,8 @@ impl fmt::Display for Error {
             IO(ref e) => write!(fmt, "IO error: {}", e),
             SystemTime(ref e) => write!(fmt, "System time error: {}", e),
             General(ref e) => write!(fmt, "Error: {}", e),
+            ProcessNotFound(pid) => write!(fmt, "Process {} not found", pid),
+            PermissionDenied(ref e) => write!(fmt, "Permission denied: {}", e),
             Unknown => write!(fmt, "An unknown error occurred"),
         }
     }
@@ -315,6 +492,8 @@ impl std::error::Error for Error {
             IO(_) => "io error",
             SystemTime(_) => "system time",
             General(_) => "general error",
+            ProcessNotFound(_) => "process not found",
+            PermissionDenied(_) => "permission denied",
             Unknown => "unknown error",
         }
     }
@@ -327,6 +506,8 @@ impl std::error::Error for Error {
             IO(ref e) => Some(e),
             SystemTime(ref e) => Some(e),
             General(_) => None,
+            ProcessNotFound(_) => None,
+            PermissionDenied(ref e) => Some(e),
             Unknown => None,
         }
     }
@@ -375,6 +556,9 @@ extern "C" {
     fn get_disk_info() -> DiskInfo;
     #[cfg(any(target_os = "freebsd", target_os = "openbsd", target_os = "netbsd"))]
     fn get_disk_info_bsd(di: &mut DiskInfo) -> i32;
+
+    #[cfg(target_os = "windows")]
+    fn get_uptime_ms() -> u64;
 }
 
 
@@ -518,6 +702,32 @@ pub fn linux_os_release() -> Result<LinuxOSReleaseInfo, Error> {
     Ok(info)
 }
 
+/// Get the kernel version and the other uname fields.
+pub fn kernel_version() -> Result<KernelVersion, Error> {
+    #[cfg(unix)]
+    {
+        let mut name: libc::utsname = unsafe { std::mem::zeroed() };
+        if unsafe { libc::uname(&mut name) } < 0 {
+            return Err(Error::IO(io::Error::last_os_error()));
+        }
+        let field = |buf: &[libc::c_char]| unsafe {
+            ffi::CStr::from_ptr(buf.as_ptr()).to_string_lossy().into_owned()
+        };
+        let release = field(&name.release);
+        let mut version = KernelVersion::parse(&release)
+            .ok_or_else(|| Error::General(format!("unknown kernel release: {}", release)))?;
+        version.sysname = field(&name.sysname);
+        version.nodename = field(&name.nodename);
+        version.version = field(&name.version);
+        version.machine = field(&name.machine);
+        Ok(version)
+    }
+    #[cfg(not(unix))]
+    {
+        Err(Error::UnsupportedSystem)
+    }
+}
+
 fn parse_line_for_linux_os_release(l: String) -> Option<(String, String)> {
     let words: Vec<&str> = l.splitn(2, '=').collect();
     if words.len() < 2 {
@@ -812,6 +1022,10 @@ pub fn hostname() -> Result<String, Error> {
 }
 
 /// Get system boottime
+///
+/// Notice, on Mac OS X and the BSDs this is the time the system booted, while on Linux,
+/// illumos, Solaris and Haiku it is the time elapsed since then. Prefer `boot_time()` and
+/// `uptime()`, which behave the same on all systems.
 #[cfg(not(windows))]
 pub fn boottime() -> Result<timeval, Error> {
     let mut bt = timeval {
@@ -821,14 +1035,9 @@ pub fn boottime() -> Result<timeval, Error> {
 
     #[cfg(any(target_os = "linux", target_os="android"))]
     {
-        let mut s = String::new();
-        File::open("/proc/uptime")?.read_to_string(&mut s)?;
-        let secs = s.trim().split(' ')
-            .take(2)
-            .map(|val| val.parse::<f64>().unwrap())
-            .collect::<Vec<f64>>();
-        bt.tv_sec = secs[0] as libc::time_t;
-        bt.tv_usec = secs[1] as libc::suseconds_t;
+        let (uptime, _) = proc_uptime()?;
+        bt.tv_sec = uptime as libc::time_t;
+        bt.tv_usec = (uptime.fract() * 1_000_000.0) as libc::suseconds_t;
 	    return Ok(bt);
     }
     #[cfg(any(target_vendor = "apple", target_os="freebsd", target_os = "openbsd", target_os = "netbsd"))]
@@ -876,6 +1085,114 @@ pub fn boottime() -> Result<timeval, Error> {
     Err(Error::UnsupportedSystem)
 }
 
+/// Get the time elapsed since the system booted.
+///
+/// On Linux this includes time spent in suspend.
+pub fn uptime() -> Result<Duration, Error> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        let mut ts: libc::timespec = unsafe { std::mem::zeroed() };
+        if unsafe { libc::clock_gettime(libc::CLOCK_BOOTTIME, &mut ts) } < 0 {
+            return Err(Error::IO(io::Error::last_os_error()));
+        }
+        Ok(Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32))
+    }
+    #[cfg(target_os = "windows")]
+    {
+        Ok(Duration::from_millis(unsafe { get_uptime_ms() }))
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "android", target_os = "windows")))]
+    {
+        Ok(SystemTime::now().duration_since(boot_time()?)?)
+    }
+}
+
+/// Get the time the system booted.
+pub fn boot_time() -> Result<SystemTime, Error> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        let mut s = String::new();
+        File::open("/proc/stat")?.read_to_string(&mut s)?;
+        s.lines()
+            .find(|line| line.starts_with("btime "))
+            .and_then(|line| line["btime ".len()..].trim().parse::<u64>().ok())
+            .map(|secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+            .ok_or(Error::Unknown)
+    }
+    #[cfg(any(target_vendor = "apple", target_os = "freebsd", target_os = "openbsd", target_os = "netbsd"))]
+    {
+        let bt = boottime()?;
+        Ok(SystemTime::UNIX_EPOCH + Duration::new(bt.tv_sec as u64, bt.tv_usec as u32 * 1000))
+    }
+    #[cfg(any(target_os = "solaris", target_os = "illumos"))]
+    {
+        Ok(SystemTime::UNIX_EPOCH + Duration::from_secs(kstat::boot_time()?))
+    }
+    #[cfg(target_os = "haiku")]
+    {
+        unsafe {
+            let mut sysinfo: libc::system_info = std::mem::zeroed();
+            if libc::get_system_info(&mut sysinfo) != libc::B_OK {
+                return Err(Error::IO(io::Error::last_os_error()));
+            }
+            Ok(SystemTime::UNIX_EPOCH + Duration::from_micros(sysinfo.boot_time as u64))
+        }
+    }
+    #[cfg(target_os = "windows")]
+    {
+        SystemTime::now().checked_sub(uptime()?).ok_or(Error::Unknown)
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "android", target_vendor = "apple", target_os = "freebsd", target_os = "openbsd", target_os = "netbsd", target_os = "solaris", target_os = "illumos", target_os = "haiku", target_os = "windows")))]
+    {
+        Err(Error::UnsupportedSystem)
+    }
+}
+
+/// Get the total time CPUs have been idle since the system booted.
+///
+/// Notice, it is summed over all CPUs, so it can be larger than `uptime()`.
+pub fn idle_time() -> Result<Duration, Error> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        let (_, idle) = proc_uptime()?;
+        Ok(Duration::from_millis((idle * 1000.0) as u64))
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    {
+        Err(Error::UnsupportedSystem)
+    }
+}
+
+/// Read uptime and idle time in seconds from /proc/uptime.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn proc_uptime() -> Result<(f64, f64), Error> {
+    let mut s = String::new();
+    File::open("/proc/uptime")?.read_to_string(&mut s)?;
+    let mut secs = s.split_whitespace().map(|val| val.parse::<f64>().ok());
+    match (secs.next(), secs.next()) {
+        (Some(Some(uptime)), Some(Some(idle))) => Ok((uptime, idle)),
+        _ => Err(Error::Unknown),
+    }
+}
+
+/// Read a small file, such as one in `/sys`, without surrounding whitespace.
+#[cfg(target_os = "linux")]
+pub(crate) fn read_trimmed<P: AsRef<std::path::Path>>(path: P) -> io::Result<String> {
+    let mut s = String::new();
+    File::open(path)?.read_to_string(&mut s)?;
+    Ok(s.trim().to_string())
+}
+
+/// The entry in brackets, from a list like "always [madvise] never" or
+/// "lzo [lzo-rle] lz4 zstd". A single entry without brackets is returned as is.
+#[cfg(target_os = "linux")]
+pub(crate) fn selected(s: &str) -> String {
+    s.split_whitespace()
+        .find_map(|word| word.strip_prefix('[')?.strip_suffix(']'))
+        .unwrap_or_else(|| s.trim())
+        .to_string()
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -894,6 +1211,31 @@ mod test {
         println!("os_release(): {}", release);
     }
 
+    #[test]
+    #[cfg(unix)]
+    pub fn test_kernel_version() {
+        let version = kernel_version().unwrap();
+        assert!(version > KernelVersion::new(0, 0, 0));
+        assert!(!version.sysname.is_empty());
+        println!("kernel_version(): {:?}", version);
+    }
+
+    #[test]
+    pub fn test_parse_kernel_version() {
+        let version = KernelVersion::parse("5.15.0-91-generic").unwrap();
+        assert_eq!((version.major, version.minor, version.patch), (5, 15, 0));
+        assert_eq!(version.extra, "-91-generic");
+        assert_eq!(version.to_string(), "5.15.0-91-generic");
+        assert!(version >= KernelVersion::new(5, 8, 0));
+        assert!(version < KernelVersion::new(5, 15, 1));
+
+        let version = KernelVersion::parse("6.1").unwrap();
+        assert_eq!(version, KernelVersion::new(6, 1, 0));
+        assert_eq!(KernelVersion::parse("4.19.112+").unwrap().extra, "+");
+        assert_eq!(KernelVersion::parse("5.10-rc1").unwrap().extra, "-rc1");
+        assert!(KernelVersion::parse("unknown").is_none());
+    }
+
     #[test]
     pub fn test_cpu_num() {
         let num = cpu_num().unwrap();
@@ -951,10 +1293,50 @@ mod test {
         assert!(bt.tv_sec > 0 || bt.tv_usec > 0);
     }
 
+    #[test]
+    pub fn test_uptime() {
+        let uptime = uptime().unwrap();
+        assert!(uptime > Duration::from_secs(0));
+        println!("uptime(): {:?}", uptime);
+    }
+
+    #[test]
+    pub fn test_boot_time() {
+        let boot = boot_time().unwrap();
+        let elapsed = SystemTime::now().duration_since(boot).unwrap();
+        let uptime = uptime().unwrap();
+        let diff = elapsed.checked_sub(uptime).unwrap_or_else(|| uptime - elapsed);
+        assert!(diff < Duration::from_secs(5));
+        println!("boot_time(): {:?}", boot);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    pub fn test_idle_time() {
+        let idle = idle_time().unwrap();
+        println!("idle_time(): {:?}", idle);
+    }
+
     #[test]
     #[cfg(target_os = "linux")]
     pub fn test_linux_os_release() {
         let os_release = linux_os_release().unwrap();
         println!("linux_os_release(): {:?}", os_release.name)
     }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    pub fn test_read_trimmed() {
+        assert_eq!(read_trimmed("/proc/sys/kernel/ostype").unwrap(), "Linux");
+        assert!(read_trimmed("/proc/sys/kernel/no_such_file").is_err());
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    pub fn test_selected() {
+        assert_eq!(selected("always [madvise] never\n"), "madvise");
+        assert_eq!(selected("always defer [defer+madvise] madvise never"), "defer+madvise");
+        assert_eq!(selected("lzo [lzo-rle] lz4 zstd\n"), "lzo-rle");
+        assert_eq!(selected("never"), "never");
+    }
 }
@@ -0,0 +1,169 @@
+#![cfg(target_os = "linux")]
+
+//! Stable machine identifier, see machine-id(5).
+
+use std::fs::File;
+use std::io::Read;
+
+use super::{dmi_info, Error};
+
+const MACHINE_ID_PATHS: [&str; 2] = ["/etc/machine-id", "/var/lib/dbus/machine-id"];
+
+/// Get the machine ID, as 32 lowercase hex characters.
+///
+/// Reads /etc/machine-id or /var/lib/dbus/machine-id, and falls back to the DMI
+/// product UUID on systems without either. Unlike `hostname()`, it stays the same
+/// when the host is renamed.
+///
+/// Notice, the ID should be treated as confidential. Prefer `machine_id_app_specific()`
+/// when the ID leaves the machine.
+pub fn machine_id() -> Result<String, Error> {
+    for path in MACHINE_ID_PATHS.iter() {
+        let mut s = String::new();
+        if File::open(path).and_then(|mut f| f.read_to_string(&mut s)).is_err() {
+            continue;
+        }
+        if let Some(id) = normalize_id(&s) {
+            return Ok(id);
+        }
+    }
+    dmi_info()?.product_uuid
+        .and_then(|uuid| normalize_id(&uuid))
+        .ok_or(Error::Unknown)
+}
+
+/// Get an ID derived from the machine ID and `app_id`, which doesn't reveal the
+/// machine ID itself.
+///
+/// `app_id` is a 128-bit application ID, and the result is the same as
+/// `sd_id128_get_machine_app_specific()` from systemd: an HMAC-SHA256 of the
+/// application ID keyed with the machine ID, truncated to a version 4 UUID.
+pub fn machine_id_app_specific(app_id: &[u8; 16]) -> Result<String, Error> {
+    let machine_id = machine_id()?;
+    let key: Vec<u8> = (0..16)
+        .map(|i| u8::from_str_radix(&machine_id[i * 2..i * 2 + 2], 16))
+        .collect::<Result<_, _>>()
+        .map_err(|_| Error::Unknown)?;
+    let mut id = [0u8; 16];
+    id.copy_from_slice(&hmac_sha256(&key, app_id)[..16]);
+    id[6] = (id[6] & 0x0f) | 0x40;
+    id[8] = (id[8] & 0x3f) | 0x80;
+    Ok(to_hex(&id))
+}
+
+/// Turn "4C4C4544-0042-..." or "4c4c45440042...\n" into 32 lowercase hex characters.
+fn normalize_id(s: &str) -> Option<String> {
+    let id: String = s.trim().chars().filter(|&c| c != '-').collect::<String>().to_lowercase();
+    if id.len() == 32 && id.chars().all(|c| c.is_ascii_hexdigit()) && id.chars().any(|c| c != '0') {
+        Some(id)
+    } else {
+        None
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut block = [0u8; 64];
+    if key.len() > 64 {
+        block[..32].copy_from_slice(&sha256(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let mut inner: Vec<u8> = block.iter().map(|b| b ^ 0x36).collect();
+    inner.extend_from_slice(message);
+    let mut outer: Vec<u8> = block.iter().map(|b| b ^ 0x5c).collect();
+    outer.extend_from_slice(&sha256(&inner));
+    sha256(&outer)
+}
+
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// SHA-256 as specified in FIPS 180-4.
+fn sha256(data: &[u8]) -> [u8; 32] {
+    let mut h: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+    ];
+    let mut msg = data.to_vec();
+    msg.push(0x80);
+    while msg.len() % 64 != 56 {
+        msg.push(0);
+    }
+    msg.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for chunk in msg.chunks(64) {
+        let mut w = [0u32; 64];
+        for (i, word) in chunk.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+        }
+        let mut v = h;
+        for i in 0..64 {
+            let s1 = v[4].rotate_right(6) ^ v[4].rotate_right(11) ^ v[4].rotate_right(25);
+            let ch = (v[4] & v[5]) ^ (!v[4] & v[6]);
+            let t1 = v[7].wrapping_add(s1).wrapping_add(ch).wrapping_add(K[i]).wrapping_add(w[i]);
+            let s0 = v[0].rotate_right(2) ^ v[0].rotate_right(13) ^ v[0].rotate_right(22);
+            let maj = (v[0] & v[1]) ^ (v[0] & v[2]) ^ (v[1] & v[2]);
+            let t2 = s0.wrapping_add(maj);
+            v = [t1.wrapping_add(t2), v[0], v[1], v[2], v[3].wrapping_add(t1), v[4], v[5], v[6]];
+        }
+        for (h, v) in h.iter_mut().zip(v.iter()) {
+            *h = h.wrapping_add(*v);
+        }
+    }
+
+    let mut out = [0u8; 32];
+    for (i, word) in h.iter().enumerate() {
+        out[i * 4..i * 4 + 4].copy_from_slice(&word.to_be_bytes());
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_machine_id() {
+        // Minimal containers have neither a machine-id nor DMI.
+        if let Ok(id) = machine_id() {
+            assert_eq!(id.len(), 32);
+            let app_id = machine_id_app_specific(b"0123456789abcdef").unwrap();
+            assert_eq!(app_id.len(), 32);
+            assert_ne!(app_id, id);
+            println!("machine_id(): {}", id);
+        }
+    }
+
+    #[test]
+    pub fn test_normalize_id() {
+        assert_eq!(normalize_id("4C4C4544-0042-3510-8052-B4C04F4E4D32\n").unwrap(),
+                   "4c4c4544004235108052b4c04f4e4d32");
+        assert!(normalize_id("uninitialized\n").is_none());
+        assert!(normalize_id("00000000-0000-0000-0000-000000000000").is_none());
+    }
+
+    #[test]
+    pub fn test_hmac_sha256() {
+        assert_eq!(to_hex(&sha256(b"abc")),
+                   "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        // RFC 4231, test case 2.
+        assert_eq!(to_hex(&hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+                   "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+    }
+}
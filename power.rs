@@ -0,0 +1,229 @@
+#![cfg(target_os = "linux")]
+
+//! Batteries and other power supplies, from `/sys/class/power_supply`.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::time::Duration;
+
+use super::Error;
+
+const POWER_SUPPLY: &str = "/sys/class/power_supply";
+
+/// Type of a power supply.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PowerSupplyKind {
+    Battery,
+    /// AC adapter.
+    Mains,
+    Usb,
+    Ups,
+    /// Such as "Wireless".
+    Other(String),
+}
+
+/// Charging status of a battery.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerSupplyStatus {
+    Charging,
+    Discharging,
+    /// Plugged in, but not charging, such as when held below a charge threshold.
+    NotCharging,
+    Full,
+    Unknown,
+}
+
+/// A power supply. Fields the driver doesn't report are `None`.
+#[derive(Debug, Clone)]
+pub struct PowerSupply {
+    /// Such as "BAT0" or "AC".
+    pub name: String,
+    pub kind: PowerSupplyKind,
+    /// Whether an adapter is plugged in. Usually not reported by batteries.
+    pub online: Option<bool>,
+    pub status: Option<PowerSupplyStatus>,
+    /// Charge level, in percent.
+    pub capacity: Option<u8>,
+    /// Energy left, in watt-hours.
+    pub energy_now: Option<f64>,
+    /// Energy when last fully charged, in watt-hours.
+    pub energy_full: Option<f64>,
+    /// Energy the battery was designed to hold, in watt-hours.
+    pub energy_full_design: Option<f64>,
+    /// Power drawn from or charged into the battery, in watts.
+    pub power_now: Option<f64>,
+    /// Voltage, in volts.
+    pub voltage_now: Option<f64>,
+    pub cycle_count: Option<u32>,
+    /// Such as "Li-ion" or "Li-poly".
+    pub technology: Option<String>,
+    /// Estimated time until empty, only while discharging.
+    pub time_to_empty: Option<Duration>,
+}
+
+/// Get the power supplies, such as batteries and AC adapters.
+///
+/// Returns an empty list on systems without any, such as most servers.
+pub fn power_supplies() -> Result<Vec<PowerSupply>, Error> {
+    let mut names: Vec<String> = match fs::read_dir(POWER_SUPPLY) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect(),
+        Err(_) => return Ok(Vec::new()),
+    };
+    names.sort();
+    let mut supplies = Vec::new();
+    for name in names {
+        let mut s = String::new();
+        let path = format!("{}/{}/uevent", POWER_SUPPLY, name);
+        if File::open(path).and_then(|mut f| f.read_to_string(&mut s)).is_ok() {
+            supplies.push(parse_uevent(&name, &s));
+        }
+    }
+    Ok(supplies)
+}
+
+/// Parse the `POWER_SUPPLY_*` properties in a uevent file.
+///
+/// The kernel reports energy in µWh, power in µW, voltage in µV, charge in µAh and
+/// current in µA. Batteries reporting charge instead of energy are converted using
+/// the current voltage.
+fn parse_uevent(name: &str, s: &str) -> PowerSupply {
+    let props: HashMap<&str, &str> = s.lines()
+        .filter_map(|line| line.strip_prefix("POWER_SUPPLY_"))
+        .filter_map(|line| {
+            let mut kv = line.splitn(2, '=');
+            Some((kv.next()?, kv.next()?.trim()))
+        })
+        .collect();
+    let micro = |key: &str| props.get(key).and_then(|v| v.parse::<f64>().ok()).map(|v| v / 1e6);
+
+    let voltage_now = micro("VOLTAGE_NOW");
+    let energy = |energy_key: &str, charge_key: &str| {
+        micro(energy_key).or_else(|| Some(micro(charge_key)? * voltage_now?))
+    };
+    let energy_now = energy("ENERGY_NOW", "CHARGE_NOW");
+    let power_now = micro("POWER_NOW")
+        .or_else(|| Some(micro("CURRENT_NOW")? * voltage_now?))
+        .map(f64::abs);
+
+    let kind = match props.get("TYPE").copied().unwrap_or("") {
+        "Battery" => PowerSupplyKind::Battery,
+        "Mains" => PowerSupplyKind::Mains,
+        "USB" => PowerSupplyKind::Usb,
+        "UPS" => PowerSupplyKind::Ups,
+        other => PowerSupplyKind::Other(other.to_string()),
+    };
+    let status = props.get("STATUS").map(|status| match *status {
+        "Charging" => PowerSupplyStatus::Charging,
+        "Discharging" => PowerSupplyStatus::Discharging,
+        "Not charging" => PowerSupplyStatus::NotCharging,
+        "Full" => PowerSupplyStatus::Full,
+        _ => PowerSupplyStatus::Unknown,
+    });
+    let time_to_empty = if status == Some(PowerSupplyStatus::Discharging) {
+        props.get("TIME_TO_EMPTY_NOW")
+            .and_then(|v| v.parse::<u64>().ok())
+            .map(Duration::from_secs)
+            .or_else(|| match (energy_now, power_now) {
+                (Some(energy), Some(power)) if power > 0.0 =>
+                    Some(Duration::from_secs_f64(energy / power * 3600.0)),
+                _ => None,
+            })
+    } else {
+        None
+    };
+
+    PowerSupply {
+        name: name.to_string(),
+        kind,
+        // 0 is offline, 1 online and 2 online with programmable voltage, as USB-PD.
+        online: props.get("ONLINE").map(|v| *v != "0"),
+        status,
+        capacity: props.get("CAPACITY").and_then(|v| v.parse::<u8>().ok()),
+        energy_now,
+        energy_full: energy("ENERGY_FULL", "CHARGE_FULL"),
+        energy_full_design: energy("ENERGY_FULL_DESIGN", "CHARGE_FULL_DESIGN"),
+        power_now,
+        voltage_now,
+        cycle_count: props.get("CYCLE_COUNT").and_then(|v| v.parse::<u32>().ok()),
+        technology: props.get("TECHNOLOGY").map(|v| v.to_string()),
+        time_to_empty,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_power_supplies() {
+        let supplies = power_supplies().unwrap();
+        println!("power_supplies(): {:?}", supplies);
+    }
+
+    #[test]
+    pub fn test_parse_uevent_battery() {
+        let s = "POWER_SUPPLY_NAME=BAT0\n\
+                 POWER_SUPPLY_TYPE=Battery\n\
+                 POWER_SUPPLY_STATUS=Discharging\n\
+                 POWER_SUPPLY_PRESENT=1\n\
+                 POWER_SUPPLY_TECHNOLOGY=Li-poly\n\
+                 POWER_SUPPLY_CYCLE_COUNT=312\n\
+                 POWER_SUPPLY_VOLTAGE_NOW=12000000\n\
+                 POWER_SUPPLY_POWER_NOW=10000000\n\
+                 POWER_SUPPLY_ENERGY_FULL_DESIGN=57000000\n\
+                 POWER_SUPPLY_ENERGY_FULL=50000000\n\
+                 POWER_SUPPLY_ENERGY_NOW=25000000\n\
+                 POWER_SUPPLY_CAPACITY=50\n";
+        let bat = parse_uevent("BAT0", s);
+        assert_eq!(bat.kind, PowerSupplyKind::Battery);
+        assert_eq!(bat.status, Some(PowerSupplyStatus::Discharging));
+        assert_eq!(bat.online, None);
+        assert_eq!(bat.capacity, Some(50));
+        assert_eq!(bat.energy_now, Some(25.0));
+        assert_eq!(bat.energy_full, Some(50.0));
+        assert_eq!(bat.energy_full_design, Some(57.0));
+        assert_eq!(bat.power_now, Some(10.0));
+        assert_eq!(bat.voltage_now, Some(12.0));
+        assert_eq!(bat.cycle_count, Some(312));
+        assert_eq!(bat.technology.as_deref(), Some("Li-poly"));
+        assert_eq!(bat.time_to_empty, Some(Duration::from_secs(9000)));
+    }
+
+    #[test]
+    pub fn test_parse_uevent_charge() {
+        let s = "POWER_SUPPLY_TYPE=Battery\n\
+                 POWER_SUPPLY_STATUS=Charging\n\
+                 POWER_SUPPLY_VOLTAGE_NOW=10000000\n\
+                 POWER_SUPPLY_CURRENT_NOW=1500000\n\
+                 POWER_SUPPLY_CHARGE_NOW=2000000\n\
+                 POWER_SUPPLY_CHARGE_FULL=4000000\n";
+        let bat = parse_uevent("BAT1", s);
+        assert_eq!(bat.energy_now, Some(20.0));
+        assert_eq!(bat.energy_full, Some(40.0));
+        assert_eq!(bat.energy_full_design, None);
+        assert_eq!(bat.power_now, Some(15.0));
+        assert_eq!(bat.time_to_empty, None);
+    }
+
+    #[test]
+    pub fn test_parse_uevent_mains() {
+        let ac = parse_uevent("AC", "POWER_SUPPLY_NAME=AC\nPOWER_SUPPLY_TYPE=Mains\nPOWER_SUPPLY_ONLINE=1\n");
+        assert_eq!(ac.kind, PowerSupplyKind::Mains);
+        assert_eq!(ac.online, Some(true));
+        assert_eq!(ac.status, None);
+    }
+
+    #[test]
+    pub fn test_parse_uevent_usb_pd() {
+        let usb = parse_uevent("ucsi-source-psy-USBC000:001",
+                               "POWER_SUPPLY_TYPE=USB\nPOWER_SUPPLY_ONLINE=2\n");
+        assert_eq!(usb.kind, PowerSupplyKind::Usb);
+        assert_eq!(usb.online, Some(true));
+        let usb = parse_uevent("usb", "POWER_SUPPLY_TYPE=USB\nPOWER_SUPPLY_ONLINE=0\n");
+        assert_eq!(usb.online, Some(false));
+    }
+}
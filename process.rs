@@ -0,0 +1,1254 @@
+#![cfg(target_os = "linux")]
+
+//! Per-process information read from `/proc/[pid]`.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use super::{cpu_num, Error};
+
+/// A handle to a running process.
+///
+/// Creating the handle only checks that the process exists. Every accessor reads
+/// `/proc/[pid]` again when it is called, so the values are always current.
+///
+/// Accessors return `Error::ProcessNotFound` if the process has exited in the
+/// meantime, and `Error::PermissionDenied` if the file is not readable by the
+/// calling user (for example the environment of another user's process).
+///
+/// The handle remembers when the process started, so once the pid is reused by a
+/// new process, accessors return `Error::ProcessNotFound` instead of reading it.
+#[derive(Debug)]
+pub struct Process {
+    pid: u32,
+    root: PathBuf,
+    /// Start time in clock ticks after boot, which tells a reused pid apart.
+    start_time: u64,
+}
+
+/// An open file descriptor of a process.
+#[derive(Debug)]
+pub struct FdInfo {
+    /// The file descriptor number.
+    pub fd: u32,
+    /// What the descriptor points to, such as "/var/log/syslog" or "socket:[12345]".
+    pub target: PathBuf,
+}
+
+/// A resource limit value, see getrlimit(2).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitValue {
+    Unlimited,
+    Value(u64),
+}
+
+/// A resource limit, as listed in `/proc/[pid]/limits`.
+#[derive(Debug)]
+pub struct ResourceLimit {
+    /// Such as "Max open files".
+    pub name: String,
+    pub soft: LimitValue,
+    pub hard: LimitValue,
+    /// Such as "files" or "bytes", `None` for unitless limits like "Max nice priority".
+    pub units: Option<String>,
+}
+
+/// IO counters from `/proc/[pid]/io`.
+#[derive(Debug, Default)]
+pub struct ProcessIo {
+    /// Bytes passed to read(2) and similar calls.
+    pub rchar: u64,
+    /// Bytes passed to write(2) and similar calls.
+    pub wchar: u64,
+    pub syscr: u64,
+    pub syscw: u64,
+    /// Bytes actually fetched from the storage layer.
+    pub read_bytes: u64,
+    /// Bytes actually sent to the storage layer.
+    pub write_bytes: u64,
+    pub cancelled_write_bytes: u64,
+}
+
+/// One line of `/proc/[pid]/cgroup`.
+#[derive(Debug)]
+pub struct CgroupEntry {
+    /// Hierarchy ID, 0 for the cgroup v2 unified hierarchy.
+    pub hierarchy: u32,
+    /// Controllers bound to the hierarchy, empty for cgroup v2.
+    pub controllers: Vec<String>,
+    /// Path of the cgroup relative to the hierarchy mount point.
+    pub path: String,
+}
+
+/// A namespace the process is a member of, from `/proc/[pid]/ns`.
+#[derive(Debug)]
+pub struct Namespace {
+    /// Such as "net", "mnt" or "pid".
+    pub name: String,
+    /// Inode number identifying the namespace. Two processes are in the same
+    /// namespace if the inodes are equal.
+    pub inode: u64,
+}
+
+/// Capability sets from `/proc/[pid]/status`, as bitmasks.
+///
+/// `ambient` is zero on kernels older than 4.3, which don't report it.
+#[derive(Debug, Default)]
+pub struct Capabilities {
+    pub inheritable: u64,
+    pub permitted: u64,
+    pub effective: u64,
+    pub bounding: u64,
+    pub ambient: u64,
+}
+
+/// Summary of a process from `/proc/[pid]/stat`.
+#[derive(Debug, Clone)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    /// Parent process ID, 0 for init and kthreadd.
+    pub ppid: u32,
+    /// Name of the executable, truncated to 15 characters by the kernel.
+    pub name: String,
+    /// Such as 'R' (running), 'S' (sleeping), 'D' (disk sleep) or 'Z' (zombie).
+    pub state: char,
+    /// Effective user ID of the owner.
+    pub uid: u32,
+    /// Time spent in user mode, in clock ticks.
+    pub utime: u64,
+    /// Time spent in kernel mode, in clock ticks.
+    pub stime: u64,
+    /// Minor page faults, which did not require loading a page from disk.
+    pub minflt: u64,
+    /// Major page faults, which required loading a page from disk.
+    pub majflt: u64,
+    pub num_threads: u64,
+    /// Time the process started after system boot, in clock ticks.
+    pub start_time: u64,
+    /// Virtual memory size in KB.
+    pub vsize: u64,
+    /// Resident set size in KB.
+    pub rss: u64,
+}
+
+/// How `ProcessSampler` scales CPU usage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CpuNormalization {
+    /// 100% is one fully used core, so a busy multi-threaded process can exceed 100%.
+    SingleCore,
+    /// 100% is all cores fully used, as counted by `cpu_num()`.
+    AllCores,
+}
+
+/// Resource usage of a process between two samples.
+#[derive(Debug)]
+pub struct ProcessUsage {
+    pub pid: u32,
+    /// CPU usage in percent, scaled as configured on the sampler.
+    pub cpu_percent: f64,
+    /// Resident set size in KB.
+    pub rss: u64,
+    /// Proportional set size in KB, from `/proc/[pid]/smaps_rollup`.
+    ///
+    /// `None` on kernels older than 4.14, or if the caller may not read it.
+    pub pss: Option<u64>,
+    /// Swapped out memory in KB.
+    pub swap: u64,
+    pub minor_faults_per_sec: f64,
+    pub major_faults_per_sec: f64,
+}
+
+/// Tracks processes across calls to report CPU usage and fault rates, like top.
+///
+/// Rates are computed against the previous sample of the same pid, so the first
+/// sample of a process reports zero rates.
+#[derive(Debug)]
+pub struct ProcessSampler {
+    normalization: CpuNormalization,
+    ticks_per_sec: f64,
+    cpus: f64,
+    last: HashMap<u32, (Instant, ProcessInfo)>,
+}
+
+/// Counters from getrusage(2).
+#[derive(Debug, Default)]
+pub struct RUsage {
+    pub user_time: Duration,
+    pub system_time: Duration,
+    /// Maximum resident set size in KB.
+    pub max_rss: u64,
+    pub minor_faults: u64,
+    pub major_faults: u64,
+    pub voluntary_context_switches: u64,
+    pub involuntary_context_switches: u64,
+    /// Number of times the filesystem had to perform input.
+    pub block_input_ops: u64,
+    /// Number of times the filesystem had to perform output.
+    pub block_output_ops: u64,
+}
+
+/// Resource usage of the calling process.
+#[derive(Debug)]
+pub struct SelfUsage {
+    /// Usage of the whole process (`RUSAGE_SELF`).
+    pub process: RUsage,
+    /// Usage of the calling thread only (`RUSAGE_THREAD`).
+    pub thread: RUsage,
+    pub open_fds: u64,
+    pub threads: u64,
+    /// Virtual memory size in KB.
+    pub virtual_memory: u64,
+    /// Resident set size in KB.
+    pub resident_memory: u64,
+}
+
+/// Memory counters of a mapping, in KB.
+#[derive(Debug, Default, Clone)]
+pub struct MapUsage {
+    pub size: u64,
+    pub rss: u64,
+    pub pss: u64,
+    pub shared_clean: u64,
+    pub shared_dirty: u64,
+    pub private_clean: u64,
+    pub private_dirty: u64,
+    pub swap: u64,
+    pub anon_huge_pages: u64,
+    pub locked: u64,
+}
+
+/// One mapping from `/proc/[pid]/smaps`.
+#[derive(Debug)]
+pub struct MemoryMap {
+    /// Start address.
+    pub start: u64,
+    /// End address, exclusive.
+    pub end: u64,
+    /// Such as "r-xp": read, write, execute and private or shared.
+    pub perms: String,
+    /// Offset into the backing file.
+    pub offset: u64,
+    /// Device of the backing file, as "major:minor" in hex.
+    pub device: String,
+    /// Inode of the backing file, 0 for anonymous mappings.
+    pub inode: u64,
+    /// Backing file, or a pseudo-path such as "[heap]" or "[stack]".
+    /// `None` for anonymous mappings.
+    pub path: Option<String>,
+    pub usage: MapUsage,
+}
+
+/// What a mapping is backed by, for grouping like pmap.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum MapKind {
+    File(String),
+    Anonymous,
+    Heap,
+    Stack,
+    /// Other pseudo-paths such as "[vdso]" or "[anon:name]".
+    Special(String),
+}
+
+/// Memory counters of mappings summed by `MapKind`.
+#[derive(Debug)]
+pub struct MapSummary {
+    pub kind: MapKind,
+    /// Number of mappings.
+    pub mappings: usize,
+    pub usage: MapUsage,
+}
+
+/// Scheduling policy of a thread, see sched(7).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchedPolicy {
+    /// SCHED_OTHER, the default time-sharing policy.
+    Normal,
+    Fifo,
+    RoundRobin,
+    Batch,
+    Idle,
+    Deadline,
+    Unknown(u32),
+}
+
+/// A thread of a process, from `/proc/[pid]/task/[tid]`.
+#[derive(Debug)]
+pub struct ThreadInfo {
+    pub tid: u32,
+    /// Thread name, truncated to 15 characters by the kernel.
+    pub name: String,
+    /// Such as 'R' (running), 'S' (sleeping) or 'D' (disk sleep).
+    pub state: char,
+    /// The CPU the thread last ran on.
+    pub processor: u32,
+    /// Time spent in user mode, in clock ticks.
+    pub utime: u64,
+    /// Time spent in kernel mode, in clock ticks.
+    pub stime: u64,
+    pub voluntary_ctxt_switches: u64,
+    pub nonvoluntary_ctxt_switches: u64,
+    pub policy: SchedPolicy,
+    /// Kernel priority, as shown by ps. Negative for real-time threads.
+    pub priority: i64,
+    /// Nice value, from -20 to 19.
+    pub nice: i64,
+    /// Real-time priority, from 1 to 99 for real-time policies and 0 otherwise.
+    pub rt_priority: u32,
+}
+
+impl Process {
+    /// Get a handle to the process with the given pid.
+    pub fn new(pid: u32) -> Result<Process, Error> {
+        let root = PathBuf::from(format!("/proc/{}", pid));
+        let start_time = read_start_time(pid, &root)?;
+        Ok(Process { pid, root, start_time })
+    }
+
+    /// Get a handle to the calling process.
+    pub fn myself() -> Result<Process, Error> {
+        Process::new(std::process::id())
+    }
+
+    /// Process ID.
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// Summary of the process from `/proc/[pid]/stat`.
+    pub fn info(&self) -> Result<ProcessInfo, Error> {
+        let uid = fs::metadata(&self.root).map_err(|e| self.error(e))?.uid();
+        let s = self.read_string_unchecked("stat")?;
+        let info = parse_process_info(self.pid, uid, &s).ok_or(Error::Unknown)?;
+        if info.start_time != self.start_time {
+            return Err(Error::ProcessNotFound(self.pid));
+        }
+        Ok(info)
+    }
+
+    /// Command line arguments, including the program name.
+    ///
+    /// Empty for kernel threads and zombies.
+    pub fn cmdline(&self) -> Result<Vec<String>, Error> {
+        let buf = self.read_bytes("cmdline")?;
+        Ok(split_nul(&buf))
+    }
+
+    /// Environment variables the process was started with.
+    pub fn environ(&self) -> Result<Vec<(String, String)>, Error> {
+        let buf = self.read_bytes("environ")?;
+        Ok(split_nul(&buf).into_iter().map(|var| {
+            let mut kv = var.splitn(2, '=');
+            let key = kv.next().unwrap_or("").to_string();
+            let value = kv.next().unwrap_or("").to_string();
+            (key, value)
+        }).collect())
+    }
+
+    /// Path of the executable.
+    pub fn exe(&self) -> Result<PathBuf, Error> {
+        self.read_link("exe")
+    }
+
+    /// Current working directory.
+    pub fn cwd(&self) -> Result<PathBuf, Error> {
+        self.read_link("cwd")
+    }
+
+    /// Root directory, which differs from "/" for chrooted processes.
+    pub fn root(&self) -> Result<PathBuf, Error> {
+        self.read_link("root")
+    }
+
+    /// Number of open file descriptors.
+    pub fn fd_count(&self) -> Result<usize, Error> {
+        let entries = fs::read_dir(self.root.join("fd")).map_err(|e| self.error(e))?;
+        let count = entries.count();
+        self.check_same()?;
+        Ok(count)
+    }
+
+    /// Open file descriptors and what they point to.
+    ///
+    /// Descriptors closed while the directory is being read are skipped.
+    pub fn fds(&self) -> Result<Vec<FdInfo>, Error> {
+        let entries = fs::read_dir(self.root.join("fd")).map_err(|e| self.error(e))?;
+        let mut fds = Vec::new();
+        for entry in entries {
+            let entry = entry.map_err(|e| self.error(e))?;
+            let fd = match entry.file_name().to_str().and_then(|s| s.parse::<u32>().ok()) {
+                Some(fd) => fd,
+                None => continue,
+            };
+            match fs::read_link(entry.path()) {
+                Ok(target) => fds.push(FdInfo { fd, target }),
+                Err(ref e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(self.error(e)),
+            }
+        }
+        self.check_same()?;
+        fds.sort_by_key(|info| info.fd);
+        Ok(fds)
+    }
+
+    /// Resource limits from `/proc/[pid]/limits`.
+    pub fn limits(&self) -> Result<Vec<ResourceLimit>, Error> {
+        let s = self.read_string("limits")?;
+        Ok(parse_limits(&s))
+    }
+
+    /// IO counters from `/proc/[pid]/io`.
+    ///
+    /// Reading another user's counters requires the same permissions as ptrace.
+    pub fn io(&self) -> Result<ProcessIo, Error> {
+        let s = self.read_string("io")?;
+        let mut io = ProcessIo::default();
+        for (key, value) in parse_key_values(&s) {
+            let value = match value.parse::<u64>() {
+                Ok(value) => value,
+                Err(_) => continue,
+            };
+            match key {
+                "rchar" => io.rchar = value,
+                "wchar" => io.wchar = value,
+                "syscr" => io.syscr = value,
+                "syscw" => io.syscw = value,
+                "read_bytes" => io.read_bytes = value,
+                "write_bytes" => io.write_bytes = value,
+                "cancelled_write_bytes" => io.cancelled_write_bytes = value,
+                _ => {}
+            }
+        }
+        Ok(io)
+    }
+
+    /// Control groups the process belongs to.
+    pub fn cgroups(&self) -> Result<Vec<CgroupEntry>, Error> {
+        let s = self.read_string("cgroup")?;
+        Ok(parse_cgroups(&s))
+    }
+
+    /// Namespaces the process is a member of.
+    pub fn namespaces(&self) -> Result<Vec<Namespace>, Error> {
+        let entries = fs::read_dir(self.root.join("ns")).map_err(|e| self.error(e))?;
+        let mut namespaces = Vec::new();
+        for entry in entries {
+            let entry = entry.map_err(|e| self.error(e))?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let target = fs::read_link(entry.path()).map_err(|e| self.error(e))?;
+            // The link target looks like "net:[4026531992]".
+            let inode = target.to_str()
+                .and_then(|t| t.rsplit('[').next())
+                .and_then(|t| t.trim_end_matches(']').parse::<u64>().ok())
+                .ok_or(Error::Unknown)?;
+            namespaces.push(Namespace { name, inode });
+        }
+        self.check_same()?;
+        namespaces.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(namespaces)
+    }
+
+    /// Badness score used by the OOM killer, from 0 to 1000.
+    pub fn oom_score(&self) -> Result<i32, Error> {
+        self.read_string("oom_score")?.trim().parse::<i32>().map_err(|_| Error::Unknown)
+    }
+
+    /// Adjustment of the OOM score, from -1000 to 1000.
+    pub fn oom_score_adj(&self) -> Result<i32, Error> {
+        self.read_string("oom_score_adj")?.trim().parse::<i32>().map_err(|_| Error::Unknown)
+    }
+
+    /// Memory mappings with their usage, from `/proc/[pid]/smaps`.
+    ///
+    /// Reading another user's mappings requires the same permissions as ptrace.
+    pub fn memory_maps(&self) -> Result<Vec<MemoryMap>, Error> {
+        let s = self.read_string("smaps")?;
+        Ok(parse_smaps(&s))
+    }
+
+    /// Threads of the process.
+    ///
+    /// Threads that exit while the list is being read are skipped.
+    pub fn threads(&self) -> Result<Vec<ThreadInfo>, Error> {
+        let entries = fs::read_dir(self.root.join("task")).map_err(|e| self.error(e))?;
+        let mut threads = Vec::new();
+        for entry in entries {
+            let entry = entry.map_err(|e| self.error(e))?;
+            let tid = match entry.file_name().to_str().and_then(|s| s.parse::<u32>().ok()) {
+                Some(tid) => tid,
+                None => continue,
+            };
+            match self.thread(tid) {
+                Ok(thread) => threads.push(thread),
+                Err(ref e) if thread_vanished(e, &self.root) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        self.check_same()?;
+        threads.sort_by_key(|thread| thread.tid);
+        Ok(threads)
+    }
+
+    fn thread(&self, tid: u32) -> Result<ThreadInfo, Error> {
+        let stat = self.read_string_unchecked(&format!("task/{}/stat", tid))?;
+        let status = self.read_string_unchecked(&format!("task/{}/status", tid))?;
+        parse_thread_info(tid, &stat, &status).ok_or(Error::Unknown)
+    }
+
+    /// Capability sets of the process.
+    pub fn capabilities(&self) -> Result<Capabilities, Error> {
+        let status = self.status()?;
+        let cap = |key: &str| -> Result<u64, Error> {
+            match status.get(key) {
+                Some(value) => u64::from_str_radix(value, 16).map_err(|_| Error::Unknown),
+                None => Ok(0),
+            }
+        };
+        Ok(Capabilities {
+            inheritable: cap("CapInh")?,
+            permitted: cap("CapPrm")?,
+            effective: cap("CapEff")?,
+            bounding: cap("CapBnd")?,
+            ambient: cap("CapAmb")?,
+        })
+    }
+
+    /// Fields of `/proc/[pid]/status` by name.
+    pub(crate) fn status(&self) -> Result<HashMap<String, String>, Error> {
+        let s = self.read_string("status")?;
+        Ok(parse_key_values(&s).into_iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect())
+    }
+
+    pub(crate) fn read_string(&self, name: &str) -> Result<String, Error> {
+        let s = self.read_string_unchecked(name)?;
+        self.check_same()?;
+        Ok(s)
+    }
+
+    fn read_string_unchecked(&self, name: &str) -> Result<String, Error> {
+        let mut s = String::new();
+        File::open(self.root.join(name))
+            .and_then(|mut f| f.read_to_string(&mut s))
+            .map_err(|e| self.error(e))?;
+        Ok(s)
+    }
+
+    fn read_bytes(&self, name: &str) -> Result<Vec<u8>, Error> {
+        let mut buf = Vec::new();
+        File::open(self.root.join(name))
+            .and_then(|mut f| f.read_to_end(&mut buf))
+            .map_err(|e| self.error(e))?;
+        self.check_same()?;
+        Ok(buf)
+    }
+
+    fn read_link(&self, name: &str) -> Result<PathBuf, Error> {
+        let target = fs::read_link(self.root.join(name)).map_err(|e| self.error(e))?;
+        self.check_same()?;
+        Ok(target)
+    }
+
+    /// Check, after reading, that the pid still belongs to the same process.
+    fn check_same(&self) -> Result<(), Error> {
+        if read_start_time(self.pid, &self.root)? != self.start_time {
+            return Err(Error::ProcessNotFound(self.pid));
+        }
+        Ok(())
+    }
+
+    /// Map an error from reading `/proc/[pid]` to the matching `Error`.
+    pub(crate) fn error(&self, e: io::Error) -> Error {
+        process_error(self.pid, &self.root, e)
+    }
+}
+
+/// Whether reading a thread failed because the thread exited, while its process
+/// at `root` is still running.
+fn thread_vanished(e: &Error, root: &Path) -> bool {
+    match *e {
+        Error::IO(ref e) => e.kind() == io::ErrorKind::NotFound,
+        // Reading a thread that exits after being opened fails with ESRCH, which
+        // `process_error` maps to the whole process.
+        Error::ProcessNotFound(_) => root.exists(),
+        _ => false,
+    }
+}
+
+/// Start time of a process in clock ticks after boot, from `/proc/[pid]/stat`.
+fn read_start_time(pid: u32, root: &Path) -> Result<u64, Error> {
+    let mut s = String::new();
+    File::open(root.join("stat"))
+        .and_then(|mut f| f.read_to_string(&mut s))
+        .map_err(|e| process_error(pid, root, e))?;
+    split_stat(&s)
+        .and_then(|(_, fields)| fields.get(19)?.parse::<u64>().ok())
+        .ok_or(Error::Unknown)
+}
+
+pub(crate) fn process_error(pid: u32, root: &Path, e: io::Error) -> Error {
+    if e.raw_os_error() == Some(libc::ESRCH) {
+        return Error::ProcessNotFound(pid);
+    }
+    match e.kind() {
+        io::ErrorKind::PermissionDenied => Error::PermissionDenied(e),
+        io::ErrorKind::NotFound if !root.exists() => Error::ProcessNotFound(pid),
+        _ => Error::IO(e),
+    }
+}
+
+impl MapUsage {
+    fn add(&mut self, other: &MapUsage) {
+        self.size += other.size;
+        self.rss += other.rss;
+        self.pss += other.pss;
+        self.shared_clean += other.shared_clean;
+        self.shared_dirty += other.shared_dirty;
+        self.private_clean += other.private_clean;
+        self.private_dirty += other.private_dirty;
+        self.swap += other.swap;
+        self.anon_huge_pages += other.anon_huge_pages;
+        self.locked += other.locked;
+    }
+}
+
+impl MemoryMap {
+    /// What the mapping is backed by.
+    pub fn kind(&self) -> MapKind {
+        match self.path.as_deref() {
+            None => MapKind::Anonymous,
+            Some("[heap]") => MapKind::Heap,
+            Some(path) if path.starts_with("[stack") => MapKind::Stack,
+            Some(path) if path.starts_with('[') => MapKind::Special(path.to_string()),
+            Some(path) => MapKind::File(path.to_string()),
+        }
+    }
+}
+
+/// Sum the usage of mappings by backing file, anonymous memory, heap and stack,
+/// similar to `pmap -X`.
+///
+/// The result is ordered by RSS, largest first.
+pub fn summarize_memory_maps(maps: &[MemoryMap]) -> Vec<MapSummary> {
+    let mut summaries: HashMap<MapKind, MapSummary> = HashMap::new();
+    for map in maps {
+        let kind = map.kind();
+        let summary = summaries.entry(kind.clone()).or_insert_with(|| MapSummary {
+            kind,
+            mappings: 0,
+            usage: MapUsage::default(),
+        });
+        summary.mappings += 1;
+        summary.usage.add(&map.usage);
+    }
+    let mut summaries: Vec<MapSummary> = summaries.into_values().collect();
+    summaries.sort_by(|a, b| b.usage.rss.cmp(&a.usage.rss).then_with(|| a.kind.cmp(&b.kind)));
+    summaries
+}
+
+fn parse_smaps(s: &str) -> Vec<MemoryMap> {
+    let mut maps: Vec<MemoryMap> = Vec::new();
+    for line in s.lines() {
+        if let Some(map) = parse_smaps_header(line) {
+            maps.push(map);
+            continue;
+        }
+        let map = match maps.last_mut() {
+            Some(map) => map,
+            None => continue,
+        };
+        let mut kv = line.splitn(2, ':');
+        let (key, value) = match (kv.next(), kv.next().and_then(parse_kb)) {
+            (Some(key), Some(value)) => (key, value),
+            _ => continue,
+        };
+        let usage = &mut map.usage;
+        match key {
+            "Size" => usage.size = value,
+            "Rss" => usage.rss = value,
+            "Pss" => usage.pss = value,
+            "Shared_Clean" => usage.shared_clean = value,
+            "Shared_Dirty" => usage.shared_dirty = value,
+            "Private_Clean" => usage.private_clean = value,
+            "Private_Dirty" => usage.private_dirty = value,
+            "Swap" => usage.swap = value,
+            "AnonHugePages" => usage.anon_huge_pages = value,
+            "Locked" => usage.locked = value,
+            _ => {}
+        }
+    }
+    maps
+}
+
+/// Parse a line like "7f1c2a000000-7f1c2a021000 rw-p 00000000 00:00 0    [heap]".
+fn parse_smaps_header(line: &str) -> Option<MemoryMap> {
+    let mut rest = line;
+    let mut fields = Vec::with_capacity(5);
+    for _ in 0..5 {
+        rest = rest.trim_start();
+        let end = rest.find(' ').unwrap_or(rest.len());
+        fields.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+    let mut range = fields[0].splitn(2, '-');
+    let start = u64::from_str_radix(range.next()?, 16).ok()?;
+    let end = u64::from_str_radix(range.next()?, 16).ok()?;
+    let path = rest.trim();
+    Some(MemoryMap {
+        start,
+        end,
+        perms: fields[1].to_string(),
+        offset: u64::from_str_radix(fields[2], 16).ok()?,
+        device: fields[3].to_string(),
+        inode: fields[4].parse::<u64>().ok()?,
+        path: if path.is_empty() { None } else { Some(path.to_string()) },
+        usage: MapUsage::default(),
+    })
+}
+
+/// Get resource usage of the calling process.
+///
+/// Unlike `ProcessSampler`, this needs no access to other processes, which makes it
+/// suitable for services exporting their own health.
+pub fn self_usage() -> Result<SelfUsage, Error> {
+    let process = Process::myself()?;
+    let status = process.status()?;
+    let field = |key: &str| status.get(key).and_then(|v| parse_kb(v)).ok_or(Error::Unknown);
+    Ok(SelfUsage {
+        process: getrusage(libc::RUSAGE_SELF)?,
+        thread: getrusage(libc::RUSAGE_THREAD)?,
+        open_fds: process.fd_count()? as u64,
+        threads: field("Threads")?,
+        virtual_memory: field("VmSize")?,
+        resident_memory: field("VmRSS")?,
+    })
+}
+
+fn getrusage(who: libc::c_int) -> Result<RUsage, Error> {
+    let mut ru: libc::rusage = unsafe { std::mem::zeroed() };
+    if unsafe { libc::getrusage(who, &mut ru) } < 0 {
+        return Err(Error::IO(io::Error::last_os_error()));
+    }
+    let duration = |tv: libc::timeval| {
+        Duration::new(tv.tv_sec as u64, tv.tv_usec as u32 * 1000)
+    };
+    Ok(RUsage {
+        user_time: duration(ru.ru_utime),
+        system_time: duration(ru.ru_stime),
+        max_rss: ru.ru_maxrss as u64,
+        minor_faults: ru.ru_minflt as u64,
+        major_faults: ru.ru_majflt as u64,
+        voluntary_context_switches: ru.ru_nvcsw as u64,
+        involuntary_context_switches: ru.ru_nivcsw as u64,
+        block_input_ops: ru.ru_inblock as u64,
+        block_output_ops: ru.ru_oublock as u64,
+    })
+}
+
+/// Resources of getrlimit(2), with the names and units used in `/proc/[pid]/limits`.
+const RLIMITS: [(RlimitResource, &str, Option<&str>); 16] = [
+    (libc::RLIMIT_CPU, "Max cpu time", Some("seconds")),
+    (libc::RLIMIT_FSIZE, "Max file size", Some("bytes")),
+    (libc::RLIMIT_DATA, "Max data size", Some("bytes")),
+    (libc::RLIMIT_STACK, "Max stack size", Some("bytes")),
+    (libc::RLIMIT_CORE, "Max core file size", Some("bytes")),
+    (libc::RLIMIT_RSS, "Max resident set", Some("bytes")),
+    (libc::RLIMIT_NPROC, "Max processes", Some("processes")),
+    (libc::RLIMIT_NOFILE, "Max open files", Some("files")),
+    (libc::RLIMIT_MEMLOCK, "Max locked memory", Some("bytes")),
+    (libc::RLIMIT_AS, "Max address space", Some("bytes")),
+    (libc::RLIMIT_LOCKS, "Max file locks", Some("locks")),
+    (libc::RLIMIT_SIGPENDING, "Max pending signals", Some("signals")),
+    (libc::RLIMIT_MSGQUEUE, "Max msgqueue size", Some("bytes")),
+    (libc::RLIMIT_NICE, "Max nice priority", None),
+    (libc::RLIMIT_RTPRIO, "Max realtime priority", None),
+    (libc::RLIMIT_RTTIME, "Max realtime timeout", Some("us")),
+];
+
+#[cfg(any(target_env = "gnu", target_env = "uclibc"))]
+type RlimitResource = libc::__rlimit_resource_t;
+#[cfg(not(any(target_env = "gnu", target_env = "uclibc")))]
+type RlimitResource = libc::c_int;
+
+/// Get the resource limits of the current process with getrlimit(2).
+///
+/// The limits are named and ordered as in `Process::limits()`, which reads the same
+/// values for any process.
+pub fn resource_limits() -> Result<Vec<ResourceLimit>, Error> {
+    // rlim_t is 32 bits wide on some targets.
+    #[allow(clippy::unnecessary_cast)]
+    let value = |v: libc::rlim_t| {
+        if v == libc::RLIM_INFINITY { LimitValue::Unlimited } else { LimitValue::Value(v as u64) }
+    };
+    RLIMITS.iter()
+        .map(|&(resource, name, units)| {
+            let mut rlim = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+            if unsafe { libc::getrlimit(resource, &mut rlim) } < 0 {
+                return Err(Error::IO(io::Error::last_os_error()));
+            }
+            Ok(ResourceLimit {
+                name: name.to_string(),
+                soft: value(rlim.rlim_cur),
+                hard: value(rlim.rlim_max),
+                units: units.map(String::from),
+            })
+        })
+        .collect()
+}
+
+/// Get all processes currently running.
+///
+/// Processes that exit while the list is being read are skipped.
+pub fn processes() -> Result<Vec<ProcessInfo>, Error> {
+    let mut processes = Vec::new();
+    for pid in pids()? {
+        match Process::new(pid).and_then(|p| p.info()) {
+            Ok(info) => processes.push(info),
+            Err(Error::ProcessNotFound(_)) => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(processes)
+}
+
+/// IDs of all processes currently running, in ascending order.
+pub(crate) fn pids() -> Result<Vec<u32>, Error> {
+    let mut pids = Vec::new();
+    for entry in fs::read_dir("/proc")? {
+        if let Some(pid) = entry?.file_name().to_str().and_then(|s| s.parse::<u32>().ok()) {
+            pids.push(pid);
+        }
+    }
+    pids.sort();
+    Ok(pids)
+}
+
+impl ProcessSampler {
+    pub fn new(normalization: CpuNormalization) -> Result<ProcessSampler, Error> {
+        Ok(ProcessSampler {
+            normalization,
+            ticks_per_sec: clock_ticks()? as f64,
+            cpus: cpu_num()? as f64,
+            last: HashMap::new(),
+        })
+    }
+
+    /// Sample one process.
+    pub fn sample(&mut self, pid: u32) -> Result<ProcessUsage, Error> {
+        let result = Process::new(pid).and_then(|p| self.sample_process(&p));
+        if let Err(Error::ProcessNotFound(_)) = result {
+            self.last.remove(&pid);
+        }
+        result
+    }
+
+    /// Sample all running processes.
+    ///
+    /// Processes that have exited since the last call are forgotten.
+    pub fn sample_all(&mut self) -> Result<Vec<ProcessUsage>, Error> {
+        let pids = pids()?;
+        let mut usages = Vec::with_capacity(pids.len());
+        for &pid in &pids {
+            match Process::new(pid).and_then(|p| self.sample_process(&p)) {
+                Ok(usage) => usages.push(usage),
+                Err(Error::ProcessNotFound(_)) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        self.last.retain(|pid, _| pids.binary_search(pid).is_ok());
+        Ok(usages)
+    }
+
+    fn sample_process(&mut self, process: &Process) -> Result<ProcessUsage, Error> {
+        let now = Instant::now();
+        let info = process.info()?;
+        let status = process.status()?;
+        let swap = status.get("VmSwap").and_then(|v| parse_kb(v)).unwrap_or(0);
+        let pss = match process.read_string("smaps_rollup") {
+            Ok(s) => parse_key_values(&s).into_iter()
+                .find(|&(key, _)| key == "Pss")
+                .and_then(|(_, value)| parse_kb(value)),
+            Err(Error::ProcessNotFound(pid)) => return Err(Error::ProcessNotFound(pid)),
+            Err(_) => None,
+        };
+
+        let mut usage = ProcessUsage {
+            pid: info.pid,
+            cpu_percent: 0.0,
+            rss: info.rss,
+            pss,
+            swap,
+            minor_faults_per_sec: 0.0,
+            major_faults_per_sec: 0.0,
+        };
+        if let Some(&(then, ref last)) = self.last.get(&info.pid) {
+            // A reused pid shows up with a different start time.
+            let elapsed = now.duration_since(then);
+            let secs = elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) / 1e9;
+            if last.start_time == info.start_time && secs > 0.0 {
+                let ticks = (info.utime + info.stime).saturating_sub(last.utime + last.stime);
+                usage.cpu_percent = ticks as f64 / self.ticks_per_sec / secs * 100.0;
+                if self.normalization == CpuNormalization::AllCores {
+                    usage.cpu_percent /= self.cpus;
+                }
+                usage.minor_faults_per_sec = info.minflt.saturating_sub(last.minflt) as f64 / secs;
+                usage.major_faults_per_sec = info.majflt.saturating_sub(last.majflt) as f64 / secs;
+            }
+        }
+        self.last.insert(info.pid, (now, info));
+        Ok(usage)
+    }
+}
+
+/// Clock ticks per second, the unit of times in `/proc/[pid]/stat`.
+pub(crate) fn clock_ticks() -> Result<u64, Error> {
+    let ret = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    if ret < 1 {
+        Err(Error::IO(io::Error::last_os_error()))
+    } else {
+        Ok(ret as u64)
+    }
+}
+
+pub(crate) fn page_size_kb() -> u64 {
+    let ret = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    if ret < 1024 { 4 } else { ret as u64 / 1024 }
+}
+
+/// Split a `stat` line into the command name and the fields after it,
+/// so that the first returned field is the state (field 3 in proc(5)).
+pub(crate) fn split_stat(s: &str) -> Option<(&str, Vec<&str>)> {
+    // The name is in parentheses and may itself contain spaces and parentheses.
+    let start = s.find('(')?;
+    let end = s.rfind(')')?;
+    let name = s.get(start + 1..end)?;
+    let fields = s.get(end + 1..)?.split_whitespace().collect();
+    Some((name, fields))
+}
+
+fn parse_thread_info(tid: u32, stat: &str, status: &str) -> Option<ThreadInfo> {
+    let (name, fields) = split_stat(stat)?;
+    if fields.len() < 39 {
+        return None;
+    }
+    let switches = |key: &str| -> Option<u64> {
+        parse_key_values(status).into_iter()
+            .find(|&(k, _)| k == key)
+            .and_then(|(_, v)| v.parse::<u64>().ok())
+    };
+    let policy = match fields[38].parse::<u32>().ok()? {
+        0 => SchedPolicy::Normal,
+        1 => SchedPolicy::Fifo,
+        2 => SchedPolicy::RoundRobin,
+        3 => SchedPolicy::Batch,
+        5 => SchedPolicy::Idle,
+        6 => SchedPolicy::Deadline,
+        other => SchedPolicy::Unknown(other),
+    };
+    Some(ThreadInfo {
+        tid,
+        name: name.to_string(),
+        state: fields[0].chars().next()?,
+        processor: fields[36].parse::<u32>().ok()?,
+        utime: fields[11].parse::<u64>().ok()?,
+        stime: fields[12].parse::<u64>().ok()?,
+        voluntary_ctxt_switches: switches("voluntary_ctxt_switches").unwrap_or(0),
+        nonvoluntary_ctxt_switches: switches("nonvoluntary_ctxt_switches").unwrap_or(0),
+        policy,
+        priority: fields[15].parse::<i64>().ok()?,
+        nice: fields[16].parse::<i64>().ok()?,
+        rt_priority: fields[37].parse::<u32>().ok()?,
+    })
+}
+
+fn parse_process_info(pid: u32, uid: u32, s: &str) -> Option<ProcessInfo> {
+    let (name, fields) = split_stat(s)?;
+    if fields.len() < 22 {
+        return None;
+    }
+    let num = |i: usize| fields[i].parse::<u64>().ok();
+    Some(ProcessInfo {
+        pid,
+        ppid: fields[1].parse::<u32>().ok()?,
+        name: name.to_string(),
+        state: fields[0].chars().next()?,
+        uid,
+        minflt: num(7)?,
+        majflt: num(9)?,
+        utime: num(11)?,
+        stime: num(12)?,
+        num_threads: num(17)?,
+        start_time: num(19)?,
+        vsize: num(20)? / 1024,
+        rss: num(21)? * page_size_kb(),
+    })
+}
+
+/// Parse a "1234 kB" value.
+pub(crate) fn parse_kb(s: &str) -> Option<u64> {
+    s.trim_end_matches("kB").trim().parse::<u64>().ok()
+}
+
+fn split_nul(buf: &[u8]) -> Vec<String> {
+    buf.split(|&b| b == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect()
+}
+
+/// Parse "Key: value" lines, as used by `status` and `io`.
+pub(crate) fn parse_key_values(s: &str) -> Vec<(&str, &str)> {
+    s.lines()
+        .filter_map(|line| {
+            let mut kv = line.splitn(2, ':');
+            let key = kv.next()?.trim();
+            let value = kv.next()?.trim();
+            Some((key, value))
+        })
+        .collect()
+}
+
+fn parse_limit_value(s: &str) -> Option<LimitValue> {
+    if s == "unlimited" {
+        Some(LimitValue::Unlimited)
+    } else {
+        s.parse::<u64>().ok().map(LimitValue::Value)
+    }
+}
+
+pub(crate) fn parse_limits(s: &str) -> Vec<ResourceLimit> {
+    let mut limits = Vec::new();
+    // Skip the "Limit  Soft Limit  Hard Limit  Units" header.
+    for line in s.lines().skip(1) {
+        let mut words: Vec<&str> = line.split_whitespace().collect();
+        let units = match words.last() {
+            Some(last) if parse_limit_value(last).is_none() => words.pop().map(String::from),
+            _ => None,
+        };
+        if words.len() < 3 {
+            continue;
+        }
+        let hard = words.pop().and_then(parse_limit_value);
+        let soft = words.pop().and_then(parse_limit_value);
+        if let (Some(soft), Some(hard)) = (soft, hard) {
+            limits.push(ResourceLimit {
+                name: words.join(" "),
+                soft,
+                hard,
+                units,
+            });
+        }
+    }
+    limits
+}
+
+fn parse_cgroups(s: &str) -> Vec<CgroupEntry> {
+    s.lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, ':');
+            let hierarchy = fields.next()?.parse::<u32>().ok()?;
+            let controllers = fields.next()?
+                .split(',')
+                .filter(|c| !c.is_empty())
+                .map(String::from)
+                .collect();
+            let path = fields.next()?.to_string();
+            Some(CgroupEntry { hierarchy, controllers, path })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_process_myself() {
+        let p = Process::myself().unwrap();
+        assert!(!p.cmdline().unwrap().is_empty());
+        assert!(p.exe().unwrap().is_absolute());
+        assert_eq!(p.cwd().unwrap(), std::env::current_dir().unwrap());
+        assert!(p.fd_count().unwrap() >= 3);
+        assert!(!p.namespaces().unwrap().is_empty());
+        println!("process: {:?} {:?}", p.io().unwrap(), p.capabilities().unwrap());
+    }
+
+    #[test]
+    pub fn test_process_not_found() {
+        match Process::new(u32::MAX) {
+            Err(Error::ProcessNotFound(pid)) => assert_eq!(pid, u32::MAX),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    pub fn test_process_pid_reused() {
+        let mut process = Process::myself().unwrap();
+        assert!(process.cmdline().is_ok());
+        // Pretend the pid now belongs to a process started at another time.
+        process.start_time += 1;
+        let pid = process.pid();
+        for result in [process.info().map(|_| ()), process.cmdline().map(|_| ()),
+                       process.exe().map(|_| ()), process.limits().map(|_| ()),
+                       process.fd_count().map(|_| ()), process.threads().map(|_| ())] {
+            match result {
+                Err(Error::ProcessNotFound(p)) => assert_eq!(p, pid),
+                other => panic!("unexpected result: {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_processes() {
+        let procs = processes().unwrap();
+        let me = procs.iter().find(|p| p.pid == std::process::id()).unwrap();
+        assert_eq!(me.uid, unsafe { libc::geteuid() });
+        assert!(me.rss > 0);
+        println!("processes(): {} processes", procs.len());
+    }
+
+    #[test]
+    pub fn test_process_sampler() {
+        let mut sampler = ProcessSampler::new(CpuNormalization::SingleCore).unwrap();
+        sampler.sample(std::process::id()).unwrap();
+        let mut x = 0u64;
+        for i in 0..10_000_000u64 {
+            x = x.wrapping_add(i * i);
+        }
+        let usage = sampler.sample(std::process::id()).unwrap();
+        assert!(usage.rss > 0);
+        println!("ProcessSampler::sample(): {:?} {}", usage, x);
+        assert!(!sampler.sample_all().unwrap().is_empty());
+    }
+
+    #[test]
+    pub fn test_self_usage() {
+        let usage = self_usage().unwrap();
+        assert!(usage.process.max_rss > 0);
+        assert!(usage.threads > 0);
+        assert!(usage.resident_memory > 0);
+        println!("self_usage(): {:?}", usage);
+    }
+
+    #[test]
+    pub fn test_resource_limits() {
+        let limits = resource_limits().unwrap();
+        let proc_limits = Process::myself().unwrap().limits().unwrap();
+        assert_eq!(limits.len(), proc_limits.len());
+        for (limit, proc_limit) in limits.iter().zip(&proc_limits) {
+            assert_eq!(limit.name, proc_limit.name);
+            assert_eq!(limit.soft, proc_limit.soft);
+            assert_eq!(limit.hard, proc_limit.hard);
+            assert_eq!(limit.units, proc_limit.units);
+        }
+    }
+
+    #[test]
+    pub fn test_parse_process_info() {
+        let s = "1234 (a (b) c) S 1 1234 1234 0 -1 4194560 500 0 7 0 120 30 0 0 20 0 \
+                 3 0 9000 10485760 256 18446744073709551615 1 1 0 0 0 0 0 0 0 0 0 0 17 2 0 0";
+        let info = parse_process_info(1234, 1000, s).unwrap();
+        assert_eq!(info.name, "a (b) c");
+        assert_eq!(info.state, 'S');
+        assert_eq!(info.ppid, 1);
+        assert_eq!(info.minflt, 500);
+        assert_eq!(info.majflt, 7);
+        assert_eq!(info.utime, 120);
+        assert_eq!(info.stime, 30);
+        assert_eq!(info.num_threads, 3);
+        assert_eq!(info.start_time, 9000);
+        assert_eq!(info.vsize, 10240);
+        assert_eq!(info.rss, 256 * page_size_kb());
+    }
+
+    #[test]
+    pub fn test_memory_maps() {
+        let maps = Process::myself().unwrap().memory_maps().unwrap();
+        assert!(maps.iter().any(|map| map.kind() == MapKind::Stack));
+        let summary = summarize_memory_maps(&maps);
+        let rss: u64 = maps.iter().map(|map| map.usage.rss).sum();
+        assert_eq!(summary.iter().map(|s| s.usage.rss).sum::<u64>(), rss);
+    }
+
+    #[test]
+    pub fn test_parse_smaps() {
+        let s = "00400000-00452000 r-xp 00000000 08:02 173521      /usr/bin/my app\n\
+                 Size:                328 kB\n\
+                 Rss:                 300 kB\n\
+                 Pss:                 150 kB\n\
+                 Private_Dirty:        12 kB\n\
+                 VmFlags: rd ex mr mw me dw\n\
+                 7ffc1a000000-7ffc1a021000 rw-p 00000000 00:00 0\n\
+                 Rss:                   8 kB\n\
+                 Swap:                  4 kB\n";
+        let maps = parse_smaps(s);
+        assert_eq!(maps.len(), 2);
+        assert_eq!(maps[0].start, 0x400000);
+        assert_eq!(maps[0].end, 0x452000);
+        assert_eq!(maps[0].perms, "r-xp");
+        assert_eq!(maps[0].device, "08:02");
+        assert_eq!(maps[0].inode, 173521);
+        assert_eq!(maps[0].kind(), MapKind::File("/usr/bin/my app".to_string()));
+        assert_eq!(maps[0].usage.pss, 150);
+        assert_eq!(maps[0].usage.private_dirty, 12);
+        assert_eq!(maps[1].kind(), MapKind::Anonymous);
+        assert_eq!(maps[1].usage.swap, 4);
+    }
+
+    #[test]
+    pub fn test_threads() {
+        let threads = Process::myself().unwrap().threads().unwrap();
+        assert!(threads.iter().any(|thread| thread.tid == std::process::id()));
+        println!("threads(): {:?}", threads);
+    }
+
+    #[test]
+    pub fn test_thread_vanished() {
+        let esrch = || io::Error::from_raw_os_error(libc::ESRCH);
+        let alive = Path::new("/proc/self");
+        assert!(thread_vanished(&process_error(1, alive, esrch()), alive));
+        assert!(thread_vanished(&Error::IO(io::ErrorKind::NotFound.into()), alive));
+        let gone = Path::new("/proc/no-such-process");
+        assert!(!thread_vanished(&process_error(1, gone, esrch()), gone));
+        assert!(!thread_vanished(&Error::Unknown, alive));
+    }
+
+    #[test]
+    pub fn test_parse_thread_info() {
+        let stat = "4321 (worker 1) R 1 1234 1234 0 -1 4194368 10 0 0 0 250 40 0 0 -51 0 \
+                    9 0 9100 10485760 256 18446744073709551615 1 1 0 0 0 0 0 0 0 0 0 0 -1 3 50 1 0 0 0";
+        let status = "Name:\tworker 1\nvoluntary_ctxt_switches:\t17\nnonvoluntary_ctxt_switches:\t5\n";
+        let thread = parse_thread_info(4321, stat, status).unwrap();
+        assert_eq!(thread.name, "worker 1");
+        assert_eq!(thread.state, 'R');
+        assert_eq!(thread.processor, 3);
+        assert_eq!(thread.utime, 250);
+        assert_eq!(thread.stime, 40);
+        assert_eq!(thread.priority, -51);
+        assert_eq!(thread.rt_priority, 50);
+        assert_eq!(thread.policy, SchedPolicy::Fifo);
+        assert_eq!(thread.voluntary_ctxt_switches, 17);
+        assert_eq!(thread.nonvoluntary_ctxt_switches, 5);
+    }
+
+    #[test]
+    pub fn test_parse_limits() {
+        let s = "Limit                     Soft Limit           Hard Limit           Units     \n\
+                 Max cpu time              unlimited            unlimited            seconds   \n\
+                 Max open files            1024                 524288               files     \n\
+                 Max nice priority         0                    0                    \n";
+        let limits = parse_limits(s);
+        assert_eq!(limits.len(), 3);
+        assert_eq!(limits[0].name, "Max cpu time");
+        assert_eq!(limits[0].soft, LimitValue::Unlimited);
+        assert_eq!(limits[1].soft, LimitValue::Value(1024));
+        assert_eq!(limits[1].hard, LimitValue::Value(524288));
+        assert_eq!(limits[1].units.as_ref().unwrap(), "files");
+        assert_eq!(limits[2].name, "Max nice priority");
+        assert!(limits[2].units.is_none());
+    }
+}
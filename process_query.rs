@@ -0,0 +1,196 @@
+#![cfg(target_os = "linux")]
+
+//! Finding processes by name, command line, owner and so on, like pgrep.
+
+use std::ffi::CString;
+use std::io;
+
+use super::{processes, Error, Process, ProcessInfo};
+
+type CmdlinePredicate = Box<dyn Fn(&str) -> bool>;
+
+/// Filters for the process list.
+///
+/// All filters that are set must match. A query without filters matches every
+/// process.
+///
+/// ```no_run
+/// use sys_info::ProcessQuery;
+///
+/// let workers = ProcessQuery::new().name("nginx").cmdline_contains("worker").run().unwrap();
+/// println!("{} nginx workers", workers.len());
+/// ```
+#[derive(Default)]
+pub struct ProcessQuery {
+    name: Option<String>,
+    cmdline_contains: Option<String>,
+    cmdline_matches: Option<CmdlinePredicate>,
+    uid: Option<u32>,
+    user: Option<String>,
+    cgroup_prefix: Option<String>,
+    ppid: Option<u32>,
+}
+
+impl ProcessQuery {
+    pub fn new() -> ProcessQuery {
+        Default::default()
+    }
+
+    /// Match the process name (comm) exactly.
+    ///
+    /// The kernel truncates names to 15 characters, so longer names never match.
+    pub fn name(mut self, name: &str) -> ProcessQuery {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    /// Match processes whose command line, with arguments joined by spaces,
+    /// contains `pattern`.
+    pub fn cmdline_contains(mut self, pattern: &str) -> ProcessQuery {
+        self.cmdline_contains = Some(pattern.to_string());
+        self
+    }
+
+    /// Match processes whose command line, with arguments joined by spaces,
+    /// satisfies `predicate`. Use this to match with a regex.
+    pub fn cmdline_matches<F>(mut self, predicate: F) -> ProcessQuery
+        where F: Fn(&str) -> bool + 'static
+    {
+        self.cmdline_matches = Some(Box::new(predicate));
+        self
+    }
+
+    /// Match processes owned by the user ID.
+    pub fn uid(mut self, uid: u32) -> ProcessQuery {
+        self.uid = Some(uid);
+        self
+    }
+
+    /// Match processes owned by the user name.
+    pub fn user(mut self, user: &str) -> ProcessQuery {
+        self.user = Some(user.to_string());
+        self
+    }
+
+    /// Match processes in a cgroup whose path starts with `prefix`, in any hierarchy.
+    pub fn cgroup_prefix(mut self, prefix: &str) -> ProcessQuery {
+        self.cgroup_prefix = Some(prefix.to_string());
+        self
+    }
+
+    /// Match direct children of the process.
+    pub fn ppid(mut self, ppid: u32) -> ProcessQuery {
+        self.ppid = Some(ppid);
+        self
+    }
+
+    /// Get the matching processes, ordered by pid.
+    pub fn run(&self) -> Result<Vec<ProcessInfo>, Error> {
+        let uid = match self.user {
+            Some(ref user) => Some(uid_by_name(user)?),
+            None => None,
+        };
+        let mut matches = Vec::new();
+        for info in processes()? {
+            match self.matches(&info, uid) {
+                Ok(true) => matches.push(info),
+                Ok(false) | Err(Error::ProcessNotFound(_)) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(matches)
+    }
+
+    /// Get the number of matching processes.
+    pub fn count(&self) -> Result<usize, Error> {
+        Ok(self.run()?.len())
+    }
+
+    fn matches(&self, info: &ProcessInfo, user_uid: Option<u32>) -> Result<bool, Error> {
+        if rejects(self.name.as_ref(), &info.name)
+            || rejects(self.uid.as_ref(), &info.uid)
+            || rejects(user_uid.as_ref(), &info.uid)
+            || rejects(self.ppid.as_ref(), &info.ppid)
+        {
+            return Ok(false);
+        }
+
+        // The remaining filters need more files from /proc.
+        let process = Process::new(info.pid)?;
+        if self.cmdline_contains.is_some() || self.cmdline_matches.is_some() {
+            let cmdline = process.cmdline()?.join(" ");
+            if let Some(ref pattern) = self.cmdline_contains {
+                if !cmdline.contains(pattern.as_str()) {
+                    return Ok(false);
+                }
+            }
+            if let Some(ref predicate) = self.cmdline_matches {
+                if !predicate(&cmdline) {
+                    return Ok(false);
+                }
+            }
+        }
+        if let Some(ref prefix) = self.cgroup_prefix {
+            if !process.cgroups()?.iter().any(|cgroup| cgroup.path.starts_with(prefix.as_str())) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Whether a filter is set and `value` doesn't match it.
+fn rejects<T: PartialEq>(filter: Option<&T>, value: &T) -> bool {
+    match filter {
+        Some(expected) => expected != value,
+        None => false,
+    }
+}
+
+fn uid_by_name(name: &str) -> Result<u32, Error> {
+    let cname = CString::new(name).map_err(|_| Error::General(format!("invalid user name: {}", name)))?;
+    let mut pwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut buf = vec![0 as libc::c_char; 4096];
+    let mut result: *mut libc::passwd = std::ptr::null_mut();
+    loop {
+        let ret = unsafe {
+            libc::getpwnam_r(cname.as_ptr(), &mut pwd, buf.as_mut_ptr(), buf.len(), &mut result)
+        };
+        match ret {
+            0 if result.is_null() => return Err(Error::General(format!("unknown user: {}", name))),
+            0 => break,
+            libc::ERANGE => {
+                let len = buf.len() * 2;
+                buf.resize(len, 0);
+            }
+            errno => return Err(Error::IO(io::Error::from_raw_os_error(errno))),
+        }
+    }
+    Ok(pwd.pw_uid)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_process_query() {
+        let me = Process::myself().unwrap().info().unwrap();
+        let found = ProcessQuery::new()
+            .name(&me.name)
+            .uid(me.uid)
+            .ppid(me.ppid)
+            .cmdline_matches(|cmdline| !cmdline.is_empty())
+            .run()
+            .unwrap();
+        assert!(found.iter().any(|info| info.pid == me.pid));
+        assert!(ProcessQuery::new().count().unwrap() > 0);
+        assert_eq!(ProcessQuery::new().cgroup_prefix("/no/such/cgroup").count().unwrap(), 0);
+    }
+
+    #[test]
+    pub fn test_process_query_user() {
+        assert!(ProcessQuery::new().user("root").count().unwrap() > 0);
+        assert!(ProcessQuery::new().user("no-such-user-here").run().is_err());
+    }
+}
@@ -0,0 +1,189 @@
+#![cfg(target_os = "linux")]
+
+//! Parent/child relationships between processes.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use super::{processes, Error, ProcessInfo};
+
+/// A snapshot of the process hierarchy, built from one scan of the process list.
+///
+/// Display it to get a pstree-like rendering.
+#[derive(Debug)]
+pub struct ProcessTree {
+    processes: HashMap<u32, ProcessInfo>,
+    children: HashMap<u32, Vec<u32>>,
+}
+
+impl ProcessTree {
+    /// Scan all running processes and build the tree.
+    pub fn new() -> Result<ProcessTree, Error> {
+        Ok(ProcessTree::from_processes(processes()?))
+    }
+
+    /// Build the tree from an existing process list.
+    pub fn from_processes(list: Vec<ProcessInfo>) -> ProcessTree {
+        let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+        for info in &list {
+            if info.ppid != info.pid {
+                children.entry(info.ppid).or_default().push(info.pid);
+            }
+        }
+        for pids in children.values_mut() {
+            pids.sort();
+        }
+        let processes = list.into_iter().map(|info| (info.pid, info)).collect();
+        ProcessTree { processes, children }
+    }
+
+    /// Get a process in the tree.
+    pub fn get(&self, pid: u32) -> Option<&ProcessInfo> {
+        self.processes.get(&pid)
+    }
+
+    /// Processes whose parent is not in the tree, such as init and kthreadd.
+    pub fn roots(&self) -> Vec<&ProcessInfo> {
+        let mut roots: Vec<&ProcessInfo> = self.processes.values()
+            .filter(|info| info.ppid == info.pid || !self.processes.contains_key(&info.ppid))
+            .collect();
+        roots.sort_by_key(|info| info.pid);
+        roots
+    }
+
+    /// Direct children of a process, ordered by pid.
+    pub fn children(&self, pid: u32) -> Vec<&ProcessInfo> {
+        self.child_pids(pid).iter().filter_map(|pid| self.get(*pid)).collect()
+    }
+
+    /// All processes below a process, in depth-first order.
+    pub fn descendants(&self, pid: u32) -> Vec<&ProcessInfo> {
+        let mut descendants = Vec::new();
+        let mut stack: Vec<u32> = self.child_pids(pid).iter().rev().cloned().collect();
+        while let Some(pid) = stack.pop() {
+            if let Some(info) = self.get(pid) {
+                descendants.push(info);
+            }
+            stack.extend(self.child_pids(pid).iter().rev());
+        }
+        descendants
+    }
+
+    /// Parent, grandparent and so on up to the root, starting with the parent.
+    pub fn ancestors(&self, pid: u32) -> Vec<&ProcessInfo> {
+        let mut ancestors: Vec<&ProcessInfo> = Vec::new();
+        let mut current = self.get(pid);
+        while let Some(info) = current {
+            current = self.get(info.ppid)
+                .filter(|parent| parent.pid != info.pid && ancestors.len() < self.processes.len());
+            if let Some(parent) = current {
+                ancestors.push(parent);
+            }
+        }
+        ancestors
+    }
+
+    /// Resident set size in KB of a process and all its descendants.
+    pub fn subtree_rss(&self, pid: u32) -> u64 {
+        self.subtree(pid).map(|info| info.rss).sum()
+    }
+
+    /// CPU time in clock ticks, user and system, used by a process and all its descendants.
+    ///
+    /// Children that have exited are only included once they are waited for, and
+    /// then only in the parent's `cutime`/`cstime`, which this doesn't count.
+    pub fn subtree_cpu_time(&self, pid: u32) -> u64 {
+        self.subtree(pid).map(|info| info.utime + info.stime).sum()
+    }
+
+    /// Format the subtree under a process in pstree style.
+    pub fn format(&self, pid: u32) -> String {
+        let mut out = String::new();
+        if let Some(info) = self.get(pid) {
+            out.push_str(&format!("{}({})\n", info.name, info.pid));
+            self.format_children(pid, "", &mut out);
+        }
+        out
+    }
+
+    fn subtree<'a>(&'a self, pid: u32) -> impl Iterator<Item = &'a ProcessInfo> + 'a {
+        self.get(pid).into_iter().chain(self.descendants(pid))
+    }
+
+    fn child_pids(&self, pid: u32) -> &[u32] {
+        self.children.get(&pid).map(|pids| pids.as_slice()).unwrap_or(&[])
+    }
+
+    fn format_children(&self, pid: u32, prefix: &str, out: &mut String) {
+        let children = self.children(pid);
+        for (i, child) in children.iter().enumerate() {
+            let last = i + 1 == children.len();
+            out.push_str(prefix);
+            out.push_str(if last { "└─" } else { "├─" });
+            out.push_str(&format!("{}({})\n", child.name, child.pid));
+            let prefix = format!("{}{}", prefix, if last { "  " } else { "│ " });
+            self.format_children(child.pid, &prefix, out);
+        }
+    }
+}
+
+impl fmt::Display for ProcessTree {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        for root in self.roots() {
+            write!(fmt, "{}", self.format(root.pid))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn info(pid: u32, ppid: u32, name: &str, rss: u64) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            ppid,
+            name: name.to_string(),
+            state: 'S',
+            uid: 0,
+            utime: 1,
+            stime: 1,
+            minflt: 0,
+            majflt: 0,
+            num_threads: 1,
+            start_time: 0,
+            vsize: 0,
+            rss,
+        }
+    }
+
+    #[test]
+    pub fn test_process_tree() {
+        let tree = ProcessTree::from_processes(vec![
+            info(1, 0, "init", 10),
+            info(10, 1, "sshd", 20),
+            info(11, 10, "bash", 30),
+            info(12, 11, "vim", 40),
+            info(20, 1, "cron", 50),
+        ]);
+        let pids = |list: Vec<&ProcessInfo>| list.iter().map(|i| i.pid).collect::<Vec<u32>>();
+        assert_eq!(pids(tree.roots()), vec![1]);
+        assert_eq!(pids(tree.children(1)), vec![10, 20]);
+        assert_eq!(pids(tree.descendants(1)), vec![10, 11, 12, 20]);
+        assert_eq!(pids(tree.ancestors(12)), vec![11, 10, 1]);
+        assert_eq!(tree.subtree_rss(10), 90);
+        assert_eq!(tree.subtree_cpu_time(10), 6);
+        assert_eq!(tree.to_string(),
+                   "init(1)\n├─sshd(10)\n│ └─bash(11)\n│   └─vim(12)\n└─cron(20)\n");
+    }
+
+    #[test]
+    pub fn test_process_tree_myself() {
+        let tree = ProcessTree::new().unwrap();
+        let me = std::process::id();
+        assert!(tree.get(me).is_some());
+        assert!(!tree.ancestors(me).is_empty());
+        assert!(tree.subtree_rss(me) > 0);
+    }
+}
@@ -0,0 +1,224 @@
+#![cfg(target_os = "linux")]
+
+//! Hardware sensors, from `/sys/class/hwmon` and `/sys/class/thermal`.
+
+use std::fs;
+use std::path::Path;
+
+use super::{Error, read_trimmed};
+
+const HWMON: &str = "/sys/class/hwmon";
+const THERMAL: &str = "/sys/class/thermal";
+
+/// What a sensor measures, which also determines the unit of its values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SensorKind {
+    /// Degrees Celsius.
+    Temperature,
+    /// Revolutions per minute.
+    Fan,
+    /// Volts.
+    Voltage,
+    /// Watts.
+    Power,
+}
+
+/// One sensor reading.
+#[derive(Debug)]
+pub struct SensorReading {
+    /// Such as "coretemp", "nvme" or "thermal_zone0".
+    pub chip: String,
+    pub kind: SensorKind,
+    /// Such as "Package id 0", or the channel like "temp1" if the driver has no label.
+    pub label: String,
+    pub value: f64,
+    /// Critical threshold, at which the hardware typically shuts down.
+    pub crit: Option<f64>,
+    /// Maximum threshold, at which the hardware typically throttles.
+    pub max: Option<f64>,
+}
+
+/// Get all temperature, fan, voltage and power readings.
+///
+/// Returns an empty list on systems without sensors, such as most virtual machines.
+pub fn sensors() -> Result<Vec<SensorReading>, Error> {
+    let mut readings = Vec::new();
+    for dir in subdirs(Path::new(HWMON), "hwmon") {
+        readings.extend(read_hwmon(&dir));
+    }
+    for dir in subdirs(Path::new(THERMAL), "thermal_zone") {
+        readings.extend(read_thermal_zone(&dir));
+    }
+    Ok(readings)
+}
+
+/// Entries of `dir` whose names start with `prefix`, in name order.
+fn subdirs(dir: &Path, prefix: &str) -> Vec<std::path::PathBuf> {
+    let mut dirs: Vec<_> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with(prefix))
+            .map(|entry| entry.path())
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    dirs.sort();
+    dirs
+}
+
+fn read_hwmon(dir: &Path) -> Vec<SensorReading> {
+    // Old drivers keep the attributes in the device directory.
+    let dir = if dir.join("name").exists() { dir.to_path_buf() } else { dir.join("device") };
+    let chip = read_trimmed(dir.join("name")).unwrap_or_else(|_| "unknown".to_string());
+    let mut channels: Vec<(SensorKind, String)> = match fs::read_dir(&dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                let channel = name.strip_suffix("_input")?;
+                let kind = channel_kind(channel)?;
+                Some((kind, channel.to_string()))
+            })
+            .collect(),
+        Err(_) => return Vec::new(),
+    };
+    channels.sort_by(|a, b| a.1.cmp(&b.1));
+
+    channels.into_iter()
+        .filter_map(|(kind, channel)| {
+            let scale = match kind {
+                SensorKind::Temperature | SensorKind::Voltage => 1e3,
+                SensorKind::Fan => 1.0,
+                SensorKind::Power => 1e6,
+            };
+            let value = |attr: &str| {
+                read_trimmed(dir.join(format!("{}_{}", channel, attr))).ok()
+                    .and_then(|v| v.parse::<f64>().ok())
+                    .map(|v| v / scale)
+            };
+            Some(SensorReading {
+                chip: chip.clone(),
+                kind,
+                label: read_trimmed(dir.join(format!("{}_label", channel)))
+                    .unwrap_or_else(|_| channel.clone()),
+                value: value("input")?,
+                crit: value("crit"),
+                max: value("max"),
+            })
+        })
+        .collect()
+}
+
+/// Kind of an hwmon channel like "temp1", "fan2", "in0" or "power1".
+fn channel_kind(channel: &str) -> Option<SensorKind> {
+    let number_start = channel.find(|c: char| c.is_ascii_digit())?;
+    if !channel[number_start..].chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    match &channel[..number_start] {
+        "temp" => Some(SensorKind::Temperature),
+        "fan" => Some(SensorKind::Fan),
+        "in" => Some(SensorKind::Voltage),
+        "power" => Some(SensorKind::Power),
+        _ => None,
+    }
+}
+
+fn read_thermal_zone(dir: &Path) -> Option<SensorReading> {
+    let millidegrees = |path: &Path| {
+        read_trimmed(path).ok().and_then(|v| v.parse::<f64>().ok()).map(|v| v / 1e3)
+    };
+    let value = millidegrees(&dir.join("temp"))?;
+    let mut crit = None;
+    let mut max = None;
+    for i in 0.. {
+        let trip_type = match read_trimmed(dir.join(format!("trip_point_{}_type", i))) {
+            Ok(trip_type) => trip_type,
+            Err(_) => break,
+        };
+        let temp = millidegrees(&dir.join(format!("trip_point_{}_temp", i)));
+        match trip_type.as_str() {
+            "critical" => crit = crit.or(temp),
+            "hot" | "passive" => max = max.or(temp),
+            _ => {}
+        }
+    }
+    Some(SensorReading {
+        chip: dir.file_name()?.to_string_lossy().into_owned(),
+        kind: SensorKind::Temperature,
+        label: read_trimmed(dir.join("type")).unwrap_or_default(),
+        value,
+        crit,
+        max,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn fixture(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sys-info-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for &(file, content) in files {
+            fs::write(dir.join(file), content).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    pub fn test_sensors() {
+        let readings = sensors().unwrap();
+        println!("sensors(): {:?}", readings);
+    }
+
+    #[test]
+    pub fn test_read_hwmon() {
+        let dir = fixture("hwmon", &[
+            ("name", "coretemp\n"),
+            ("temp1_input", "45000\n"),
+            ("temp1_crit", "100000\n"),
+            ("temp1_max", "80000\n"),
+            ("temp1_label", "Package id 0\n"),
+            ("fan1_input", "1200\n"),
+            ("in0_input", "1050\n"),
+            ("power1_input", "15500000\n"),
+            ("temp1_crit_alarm", "0\n"),
+        ]);
+        let readings = read_hwmon(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(readings.len(), 4);
+        let temp = readings.iter().find(|r| r.kind == SensorKind::Temperature).unwrap();
+        assert_eq!(temp.chip, "coretemp");
+        assert_eq!(temp.label, "Package id 0");
+        assert_eq!(temp.value, 45.0);
+        assert_eq!(temp.crit, Some(100.0));
+        assert_eq!(temp.max, Some(80.0));
+        let fan = readings.iter().find(|r| r.kind == SensorKind::Fan).unwrap();
+        assert_eq!((fan.label.as_str(), fan.value), ("fan1", 1200.0));
+        let volt = readings.iter().find(|r| r.kind == SensorKind::Voltage).unwrap();
+        assert_eq!(volt.value, 1.05);
+        let power = readings.iter().find(|r| r.kind == SensorKind::Power).unwrap();
+        assert_eq!(power.value, 15.5);
+    }
+
+    #[test]
+    pub fn test_read_thermal_zone() {
+        let dir = fixture("thermal_zone0", &[
+            ("type", "x86_pkg_temp\n"),
+            ("temp", "52000\n"),
+            ("trip_point_0_type", "passive\n"),
+            ("trip_point_0_temp", "95000\n"),
+            ("trip_point_1_type", "critical\n"),
+            ("trip_point_1_temp", "105000\n"),
+        ]);
+        let reading = read_thermal_zone(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(reading.label, "x86_pkg_temp");
+        assert_eq!(reading.value, 52.0);
+        assert_eq!(reading.max, Some(95.0));
+        assert_eq!(reading.crit, Some(105.0));
+    }
+}
@@ -0,0 +1,243 @@
+#![cfg(target_os = "linux")]
+
+//! Swap devices from `/proc/swaps`, with compression statistics of zram and zswap.
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use super::{Error, read_trimmed, selected};
+
+/// Type of a swap area.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SwapKind {
+    /// A block device, including zram devices.
+    Partition,
+    File,
+    Other(String),
+}
+
+/// Compression statistics of a zram device, from `/sys/block/zram*/mm_stat`.
+///
+/// Sizes are in bytes.
+#[derive(Debug, Clone, Default)]
+pub struct ZramStats {
+    /// Compression algorithm, such as "lzo-rle" or "zstd".
+    pub algorithm: String,
+    /// Size of the device.
+    pub disk_size: u64,
+    /// Uncompressed size of the data stored.
+    pub orig_data_size: u64,
+    /// Compressed size of the data stored.
+    pub compr_data_size: u64,
+    /// Memory used, including allocator overhead.
+    pub mem_used_total: u64,
+    /// Limit of the memory used, 0 for no limit.
+    pub mem_limit: u64,
+    /// Highest memory used.
+    pub mem_used_max: u64,
+    /// Pages filled with one repeated value, which use no memory.
+    pub same_pages: u64,
+    /// Pages freed by compaction.
+    pub pages_compacted: u64,
+    /// Pages stored uncompressed because they didn't compress. `None` before 4.19.
+    pub huge_pages: Option<u64>,
+}
+
+impl ZramStats {
+    /// Uncompressed over compressed size, such as 3.0 for data compressed to a third.
+    ///
+    /// `None` if the device holds no data.
+    pub fn compression_ratio(&self) -> Option<f64> {
+        if self.compr_data_size == 0 {
+            None
+        } else {
+            Some(self.orig_data_size as f64 / self.compr_data_size as f64)
+        }
+    }
+}
+
+/// An active swap area.
+#[derive(Debug, Clone)]
+pub struct SwapDevice {
+    /// Such as "/dev/sda2", "/swapfile" or "/dev/zram0".
+    pub filename: String,
+    pub kind: SwapKind,
+    /// Size in KB.
+    pub size: u64,
+    /// Used size in KB.
+    pub used: u64,
+    /// Areas with higher priority are used first.
+    pub priority: i32,
+    /// Compression statistics, if the area is a zram device.
+    pub zram: Option<ZramStats>,
+}
+
+/// Configuration and usage of zswap, the compressed cache in front of swap devices.
+#[derive(Debug, Clone)]
+pub struct ZswapInfo {
+    pub enabled: bool,
+    /// Compression algorithm, such as "lzo" or "zstd".
+    pub compressor: String,
+    /// Memory allocator of the pool, such as "zsmalloc". `None` on kernels where it
+    /// can't be chosen.
+    pub zpool: Option<String>,
+    /// Maximum size of the pool, in percent of memory.
+    pub max_pool_percent: Option<u32>,
+    /// Memory used by the pool in KB, from `/proc/meminfo`. `None` before 6.5.
+    pub pool_size: Option<u64>,
+    /// Uncompressed size of the pages stored in KB, from `/proc/meminfo`. `None` before 6.5.
+    pub stored: Option<u64>,
+}
+
+/// Get the active swap areas.
+pub fn swap_devices() -> Result<Vec<SwapDevice>, Error> {
+    let mut s = String::new();
+    File::open("/proc/swaps")?.read_to_string(&mut s)?;
+    let mut devices = parse_swaps(&s);
+    for device in &mut devices {
+        if let Some(name) = device.filename.strip_prefix("/dev/") {
+            if name.starts_with("zram") {
+                device.zram = zram_stats(name);
+            }
+        }
+    }
+    Ok(devices)
+}
+
+/// Get the zswap configuration, or `None` if the kernel is built without zswap.
+pub fn zswap() -> Result<Option<ZswapInfo>, Error> {
+    let dir = Path::new("/sys/module/zswap/parameters");
+    let enabled = match read_trimmed(dir.join("enabled")) {
+        Ok(enabled) => enabled,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(Error::IO(e)),
+    };
+    let mut meminfo = String::new();
+    File::open("/proc/meminfo")?.read_to_string(&mut meminfo)?;
+    let meminfo_kb = |key: &str| {
+        meminfo.lines()
+            .find(|line| line.split(':').next() == Some(key))
+            .and_then(|line| line.split_whitespace().nth(1))
+            .and_then(|kb| kb.parse::<u64>().ok())
+    };
+    Ok(Some(ZswapInfo {
+        enabled: enabled == "Y",
+        compressor: read_trimmed(dir.join("compressor"))?,
+        zpool: read_trimmed(dir.join("zpool")).ok(),
+        max_pool_percent: read_trimmed(dir.join("max_pool_percent")).ok()
+            .and_then(|v| v.parse::<u32>().ok()),
+        pool_size: meminfo_kb("Zswap"),
+        stored: meminfo_kb("Zswapped"),
+    }))
+}
+
+fn zram_stats(name: &str) -> Option<ZramStats> {
+    let dir = Path::new("/sys/block").join(name);
+    let mut stats = parse_mm_stat(&read_trimmed(dir.join("mm_stat")).ok()?)?;
+    stats.algorithm = read_trimmed(dir.join("comp_algorithm"))
+        .map(|s| selected(&s))
+        .unwrap_or_default();
+    stats.disk_size = read_trimmed(dir.join("disksize")).ok()?.parse::<u64>().ok()?;
+    Some(stats)
+}
+
+fn parse_swaps(s: &str) -> Vec<SwapDevice> {
+    // Skip the "Filename  Type  Size  Used  Priority" header.
+    s.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 5 {
+                return None;
+            }
+            Some(SwapDevice {
+                // The kernel escapes spaces, tabs, newlines and backslashes in octal.
+                filename: fields[0]
+                    .replace("\\040", " ")
+                    .replace("\\011", "\t")
+                    .replace("\\012", "\n")
+                    .replace("\\134", "\\"),
+                kind: match fields[1] {
+                    "partition" => SwapKind::Partition,
+                    "file" => SwapKind::File,
+                    other => SwapKind::Other(other.to_string()),
+                },
+                size: fields[2].parse::<u64>().ok()?,
+                used: fields[3].parse::<u64>().ok()?,
+                priority: fields[4].parse::<i32>().ok()?,
+                zram: None,
+            })
+        })
+        .collect()
+}
+
+fn parse_mm_stat(s: &str) -> Option<ZramStats> {
+    let values = s.split_whitespace()
+        .map(|v| v.parse::<u64>().ok())
+        .collect::<Option<Vec<u64>>>()?;
+    if values.len() < 7 {
+        return None;
+    }
+    Some(ZramStats {
+        orig_data_size: values[0],
+        compr_data_size: values[1],
+        mem_used_total: values[2],
+        mem_limit: values[3],
+        mem_used_max: values[4],
+        same_pages: values[5],
+        pages_compacted: values[6],
+        huge_pages: values.get(7).copied(),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_swap_devices() {
+        let devices = swap_devices().unwrap();
+        println!("swap_devices(): {:?}", devices);
+    }
+
+    #[test]
+    pub fn test_zswap() {
+        let zswap = zswap().unwrap();
+        println!("zswap(): {:?}", zswap);
+    }
+
+    #[test]
+    pub fn test_parse_swaps() {
+        let s = "Filename\t\t\t\tType\t\tSize\t\tUsed\t\tPriority\n\
+                 /dev/sda2                               partition\t8388604\t\t1024\t\t-2\n\
+                 /swap\\040file                           file\t\t1048572\t\t0\t\t-3\n\
+                 /dev/zram0                              partition\t4194300\t\t51200\t\t100\n";
+        let devices = parse_swaps(s);
+        assert_eq!(devices.len(), 3);
+        assert_eq!(devices[0].filename, "/dev/sda2");
+        assert_eq!(devices[0].kind, SwapKind::Partition);
+        assert_eq!(devices[0].size, 8388604);
+        assert_eq!(devices[0].used, 1024);
+        assert_eq!(devices[0].priority, -2);
+        assert_eq!(devices[1].filename, "/swap file");
+        assert_eq!(devices[1].kind, SwapKind::File);
+        assert_eq!(devices[2].priority, 100);
+    }
+
+    #[test]
+    pub fn test_parse_mm_stat() {
+        let stats = parse_mm_stat("  3145728   1048576   1310720        0   1310720       12        3        5        5").unwrap();
+        assert_eq!(stats.orig_data_size, 3145728);
+        assert_eq!(stats.compr_data_size, 1048576);
+        assert_eq!(stats.mem_used_total, 1310720);
+        assert_eq!(stats.same_pages, 12);
+        assert_eq!(stats.huge_pages, Some(5));
+        assert_eq!(stats.compression_ratio(), Some(3.0));
+        let stats = parse_mm_stat("0 0 0 0 0 0 0").unwrap();
+        assert_eq!(stats.huge_pages, None);
+        assert_eq!(stats.compression_ratio(), None);
+        assert!(parse_mm_stat("0 0 0").is_none());
+    }
+}
@@ -0,0 +1,512 @@
+//! Kernel parameters by sysctl name, such as "vm.swappiness".
+//!
+//! On Linux the names map onto files in `/proc/sys`. On Mac OS X, FreeBSD and NetBSD
+//! they are read with sysctlbyname(3).
+
+#[cfg(target_os = "linux")]
+use std::fs::{self, File};
+#[cfg(target_os = "linux")]
+use std::io::{self, Read};
+#[cfg(target_os = "linux")]
+use std::path::{Path, PathBuf};
+
+use super::Error;
+
+#[cfg(target_os = "linux")]
+const PROC_SYS: &str = "/proc/sys";
+
+/// Read a parameter as text, such as "60" for "vm.swappiness" or "4 4 1 7" for
+/// "kernel.printk".
+///
+/// On Linux, components containing dots, such as interface names, are written with
+/// '/' instead, as sysctl(8) does: "net.ipv4.conf.eth0/1.forwarding". Names in path
+/// form, like "net/ipv4/conf/eth0.1/forwarding", are accepted too.
+pub fn read(name: &str) -> Result<String, Error> {
+    #[cfg(target_os = "linux")]
+    {
+        let mut s = String::new();
+        File::open(name_to_path(name))?.read_to_string(&mut s)?;
+        Ok(s.trim_end_matches('\n').to_string())
+    }
+    #[cfg(any(target_vendor = "apple", target_os = "freebsd", target_os = "netbsd"))]
+    {
+        let ty = value_type(name)?;
+        let buf = sysctlbyname(name)?;
+        format_value(name, &buf, ty)
+    }
+}
+
+/// Read a parameter holding one integer, such as "vm.swappiness".
+pub fn read_int(name: &str) -> Result<i64, Error> {
+    let value = read(name)?;
+    value.trim().parse::<i64>()
+        .map_err(|_| Error::General(format!("{} is not an integer: {}", name, value)))
+}
+
+/// Read a parameter holding several integers, such as "kernel.printk" or
+/// "net.ipv4.ip_local_port_range".
+pub fn read_ints(name: &str) -> Result<Vec<i64>, Error> {
+    let value = read(name)?;
+    value.split_whitespace()
+        .map(|word| word.parse::<i64>())
+        .collect::<Result<Vec<i64>, _>>()
+        .map_err(|_| Error::General(format!("{} is not a list of integers: {}", name, value)))
+}
+
+/// List the names of all parameters starting with `prefix`, such as "vm." or
+/// "net.core.", in sorted order.
+///
+/// Only the subtree named by `prefix` is walked, and subtrees that cannot be read
+/// are skipped.
+pub fn list(prefix: &str) -> Result<Vec<String>, Error> {
+    // The last component of the prefix may be partial, as in "kernel.os".
+    let parent = prefix.rfind('.').map_or("", |i| &prefix[..i]);
+    #[cfg(target_os = "linux")]
+    let mut names = {
+        let dir = if parent.is_empty() { PathBuf::from(PROC_SYS) } else { name_to_path(parent) };
+        let mut names = Vec::new();
+        if dir.is_dir() {
+            collect_names(&dir, parent, &mut names)?;
+        }
+        names
+    };
+    #[cfg(any(target_vendor = "apple", target_os = "freebsd", target_os = "netbsd"))]
+    let mut names = bsd_names(parent)?;
+    names.retain(|name| name.starts_with(prefix));
+    names.sort();
+    Ok(names)
+}
+
+#[cfg(target_os = "linux")]
+fn name_to_path(name: &str) -> PathBuf {
+    let mut path = PathBuf::from(PROC_SYS);
+    // Like sysctl(8), whichever separator comes first decides the form of the name.
+    let path_form = match (name.find('.'), name.find('/')) {
+        (Some(dot), Some(slash)) => slash < dot,
+        (None, Some(_)) => true,
+        _ => false,
+    };
+    if path_form {
+        path.push(name.trim_start_matches('/'));
+    } else {
+        for component in name.split('.') {
+            path.push(component.replace('/', "."));
+        }
+    }
+    path
+}
+
+#[cfg(target_os = "linux")]
+fn collect_names(dir: &Path, prefix: &str, names: &mut Vec<String>) -> Result<(), Error> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        // Directories readable only by root, and network devices that went away.
+        Err(ref e) if e.kind() == io::ErrorKind::PermissionDenied
+            || e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(Error::IO(e)),
+    };
+    for entry in entries {
+        let entry = entry?;
+        let component = entry.file_name().to_string_lossy().replace('.', "/");
+        let name = if prefix.is_empty() { component } else { format!("{}.{}", prefix, component) };
+        if entry.file_type()?.is_dir() {
+            // binfmt_misc is a filesystem of its own, and looking into it may mount it.
+            if name != "fs.binfmt_misc" {
+                collect_names(&entry.path(), &name, names)?;
+            }
+        } else {
+            names.push(name);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(any(target_vendor = "apple", target_os = "freebsd", target_os = "netbsd"))]
+fn sysctlbyname(name: &str) -> Result<Vec<u8>, Error> {
+    use std::io;
+    use std::ptr::null_mut;
+
+    let cname = std::ffi::CString::new(name).map_err(|_| Error::Unknown)?;
+    let mut size: libc::size_t = 0;
+    unsafe {
+        if libc::sysctlbyname(cname.as_ptr(), null_mut(), &mut size, null_mut(), 0) == -1 {
+            return Err(Error::IO(io::Error::last_os_error()));
+        }
+        let mut buf = vec![0u8; size];
+        if libc::sysctlbyname(cname.as_ptr(), buf.as_mut_ptr() as *mut libc::c_void,
+                              &mut size, null_mut(), 0) == -1 {
+            return Err(Error::IO(io::Error::last_os_error()));
+        }
+        buf.truncate(size);
+        Ok(buf)
+    }
+}
+
+#[cfg(any(target_vendor = "apple", target_os = "freebsd", target_os = "netbsd"))]
+fn sysctlnametomib(name: &str) -> Result<Vec<libc::c_int>, Error> {
+    let cname = std::ffi::CString::new(name).map_err(|_| Error::Unknown)?;
+    let mut mib = vec![0 as libc::c_int; 32];
+    let mut len: libc::size_t = mib.len();
+    unsafe {
+        if libc::sysctlnametomib(cname.as_ptr(), mib.as_mut_ptr(), &mut len) == -1 {
+            return Err(Error::IO(std::io::Error::last_os_error()));
+        }
+    }
+    mib.truncate(len);
+    Ok(mib)
+}
+
+/// Call sysctl(3) with `mib`, passing `new` as the new value, and return the old value.
+#[cfg(any(target_vendor = "apple", target_os = "freebsd", target_os = "netbsd"))]
+fn sysctl_mib(mib: &mut [libc::c_int], new: &[u8]) -> Result<Vec<u8>, Error> {
+    use std::io;
+    use std::ptr::null_mut;
+
+    let newp = if new.is_empty() { null_mut() } else { new.as_ptr() as *mut libc::c_void };
+    let mut size: libc::size_t = 0;
+    unsafe {
+        if libc::sysctl(mib.as_mut_ptr(), mib.len() as libc::c_uint, null_mut(), &mut size,
+                        newp, new.len()) == -1 {
+            return Err(Error::IO(io::Error::last_os_error()));
+        }
+        let mut buf = vec![0u8; size];
+        if libc::sysctl(mib.as_mut_ptr(), mib.len() as libc::c_uint,
+                        buf.as_mut_ptr() as *mut libc::c_void, &mut size, newp, new.len()) == -1 {
+            return Err(Error::IO(io::Error::last_os_error()));
+        }
+        buf.truncate(size);
+        Ok(buf)
+    }
+}
+
+/// The type of a value, as far as formatting it is concerned.
+#[cfg(any(test, target_vendor = "apple", target_os = "freebsd", target_os = "netbsd"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ValueType {
+    String,
+    /// Integers of `size` bytes. A value may hold an array of them.
+    Int { size: usize, signed: bool },
+    /// Nodes, structures and other opaque data, such as "vm.loadavg".
+    Other,
+}
+
+/// Look up the type of a parameter with the `{0, 4}` "oidfmt" node, which returns the
+/// CTLTYPE kind followed by a format string such as "IU".
+#[cfg(any(target_vendor = "apple", target_os = "freebsd"))]
+fn value_type(name: &str) -> Result<ValueType, Error> {
+    let mut mib = vec![0, 4];
+    mib.extend(sysctlnametomib(name)?);
+    let buf = sysctl_mib(&mut mib, &[])?;
+    if buf.len() < 4 {
+        return Err(Error::Unknown);
+    }
+    let kind = u32::from_ne_bytes([buf[0], buf[1], buf[2], buf[3]]);
+    let format = buf[4..].split(|&b| b == 0).next().unwrap_or(&[]);
+    Ok(oidfmt_type(kind, &String::from_utf8_lossy(format)))
+}
+
+/// Names of the parameters below `parent`, or of all parameters if it is empty, by
+/// walking the tree with the `{0, 2}` "next" node and naming each OID with the
+/// `{0, 1}` "name" node.
+#[cfg(any(target_vendor = "apple", target_os = "freebsd"))]
+fn bsd_names(parent: &str) -> Result<Vec<String>, Error> {
+    let mut mib = match parent_mib(parent)? {
+        Some(mib) => mib,
+        None => return Ok(Vec::new()),
+    };
+    let subtree = format!("{}.", parent);
+    let mut names = Vec::new();
+    loop {
+        let mut next = vec![0, 2];
+        next.extend(&mib);
+        mib = match sysctl_mib(&mut next, &[]) {
+            Ok(buf) => buf.chunks_exact(4)
+                .map(|b| libc::c_int::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+                .collect(),
+            // The last OID of the tree.
+            Err(Error::IO(ref e)) if e.raw_os_error() == Some(libc::ENOENT) => break,
+            Err(e) => return Err(e),
+        };
+        let mut name = vec![0, 1];
+        name.extend(&mib);
+        let name = format_value("name", &sysctl_mib(&mut name, &[])?, ValueType::String)?;
+        // OIDs are visited depth first, so the subtree ends at the first name outside it.
+        if !parent.is_empty() && !name.starts_with(&subtree) {
+            break;
+        }
+        names.push(name);
+    }
+    Ok(names)
+}
+
+/// The OID of `parent`, empty for the root, or `None` if there is no such node.
+#[cfg(any(target_vendor = "apple", target_os = "freebsd", target_os = "netbsd"))]
+fn parent_mib(parent: &str) -> Result<Option<Vec<libc::c_int>>, Error> {
+    if parent.is_empty() {
+        return Ok(Some(Vec::new()));
+    }
+    match sysctlnametomib(parent) {
+        Ok(mib) => Ok(Some(mib)),
+        Err(Error::IO(ref e)) if e.raw_os_error() == Some(libc::ENOENT) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// The type for a CTLTYPE kind and format string from FreeBSD's or Mac OS X's
+/// <sys/sysctl.h>. Both agree on kinds 1 to 5; the rest are FreeBSD only.
+#[cfg(any(test, target_vendor = "apple", target_os = "freebsd"))]
+fn oidfmt_type(kind: u32, format: &str) -> ValueType {
+    let long = std::mem::size_of::<libc::c_long>();
+    let unsigned = format.contains('U');
+    let int = |size, signed| ValueType::Int { size, signed };
+    match kind & 0xf {
+        // CTLTYPE_INT. Mac OS X also reports longs as INT, with format "L" or "LU".
+        2 if format.starts_with('L') => int(long, !unsigned),
+        2 => int(4, !unsigned),
+        3 => ValueType::String,
+        // CTLTYPE_QUAD on Mac OS X, CTLTYPE_S64 on FreeBSD.
+        4 => int(8, !unsigned),
+        6 => int(4, false),
+        7 => int(long, true),
+        8 => int(long, false),
+        9 => int(8, false),
+        0xa => int(1, false),
+        0xb => int(2, false),
+        0xc => int(1, true),
+        0xd => int(2, true),
+        0xe => int(4, true),
+        0xf => int(4, false),
+        _ => ValueType::Other,
+    }
+}
+
+/// `struct sysctlnode` from NetBSD's <sys/sysctl.h>, where pointers and longs are
+/// padded to 64 bits.
+#[cfg(target_os = "netbsd")]
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct SysctlNode {
+    flags: u32,
+    num: i32,
+    name: [libc::c_char; 32],
+    ver: u32,
+    rsvd: u32,
+    un: [u64; 2],
+    size: u64,
+    func: u64,
+    parent: u64,
+    desc: u64,
+}
+
+/// Look up the type of a parameter by querying its parent node with CTL_QUERY.
+#[cfg(target_os = "netbsd")]
+fn value_type(name: &str) -> Result<ValueType, Error> {
+    let mut mib = sysctlnametomib(name)?;
+    let num = mib.pop().ok_or(Error::Unknown)?;
+    query_children(&mib)?
+        .into_iter()
+        .find(|node| node.num == num)
+        .map(|node| netbsd_type(node.flags))
+        .ok_or(Error::Unknown)
+}
+
+/// The child nodes of the node `mib`, or of the root if it is empty, with CTL_QUERY.
+#[cfg(target_os = "netbsd")]
+fn query_children(mib: &[libc::c_int]) -> Result<Vec<SysctlNode>, Error> {
+    use std::mem::size_of;
+    use std::slice;
+
+    let mut mib = mib.to_vec();
+    mib.push(-2); // CTL_QUERY
+    let mut query: SysctlNode = unsafe { std::mem::zeroed() };
+    query.flags = 0x0100_0000; // SYSCTL_VERSION
+    let query = unsafe {
+        slice::from_raw_parts(&query as *const SysctlNode as *const u8, size_of::<SysctlNode>())
+    };
+    let buf = sysctl_mib(&mut mib, query)?;
+    let chunks = buf.chunks_exact(size_of::<SysctlNode>());
+    if !chunks.remainder().is_empty() {
+        return Err(Error::Unknown);
+    }
+    Ok(chunks
+        .map(|chunk| unsafe { (chunk.as_ptr() as *const SysctlNode).read_unaligned() })
+        .collect())
+}
+
+/// Names of the parameters below `parent`, or of all parameters if it is empty.
+#[cfg(target_os = "netbsd")]
+fn bsd_names(parent: &str) -> Result<Vec<String>, Error> {
+    let mut names = Vec::new();
+    if let Some(mib) = parent_mib(parent)? {
+        collect_nodes(&mib, parent, &mut names)?;
+    }
+    Ok(names)
+}
+
+#[cfg(target_os = "netbsd")]
+fn collect_nodes(mib: &[libc::c_int], prefix: &str, names: &mut Vec<String>)
+                 -> Result<(), Error> {
+    let nodes = match query_children(mib) {
+        Ok(nodes) => nodes,
+        // A leaf, or a subtree readable only by root.
+        Err(Error::IO(ref e)) if e.raw_os_error() == Some(libc::ENOTDIR)
+            || e.raw_os_error() == Some(libc::EPERM)
+            || e.raw_os_error() == Some(libc::EACCES) => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    for node in nodes {
+        let bytes: Vec<u8> = node.name.iter().take_while(|&&c| c != 0).map(|&c| c as u8).collect();
+        let component = String::from_utf8_lossy(&bytes);
+        let name = if prefix.is_empty() {
+            component.into_owned()
+        } else {
+            format!("{}.{}", prefix, component)
+        };
+        if node.flags & 0xf == 1 { // CTLTYPE_NODE
+            let mut child = mib.to_vec();
+            child.push(node.num);
+            collect_nodes(&child, &name, names)?;
+        } else {
+            names.push(name);
+        }
+    }
+    Ok(())
+}
+
+/// The type for the flags of a NetBSD sysctl node.
+#[cfg(any(test, target_os = "netbsd"))]
+fn netbsd_type(flags: u32) -> ValueType {
+    let signed = flags & 0x0008_0000 == 0; // CTLFLAG_UNSIGNED
+    match flags & 0xf {
+        2 => ValueType::Int { size: 4, signed },
+        3 => ValueType::String,
+        4 => ValueType::Int { size: 8, signed },
+        6 => ValueType::Int { size: 1, signed: false },
+        _ => ValueType::Other,
+    }
+}
+
+/// Format a raw value the way sysctl(8) prints simple types: strings as they are, and
+/// integers as decimal numbers separated by spaces.
+#[cfg(any(test, target_vendor = "apple", target_os = "freebsd", target_os = "netbsd"))]
+fn format_value(name: &str, buf: &[u8], ty: ValueType) -> Result<String, Error> {
+    match ty {
+        ValueType::String => {
+            let text = buf.split(|&b| b == 0).next().unwrap_or(&[]);
+            Ok(String::from_utf8_lossy(text).into_owned())
+        }
+        ValueType::Int { size, signed } if buf.chunks_exact(size).remainder().is_empty() => {
+            buf.chunks(size)
+                .map(|chunk| format_int(chunk, signed))
+                .collect::<Option<Vec<String>>>()
+                .map(|values| values.join(" "))
+                .ok_or_else(|| Error::General(format!("unexpected size of {}: {}", name, size)))
+        }
+        ValueType::Int { .. } => {
+            Err(Error::General(format!("unexpected size of {}: {}", name, buf.len())))
+        }
+        ValueType::Other => Err(Error::General(format!("{} is not a string or integer", name))),
+    }
+}
+
+#[cfg(any(test, target_vendor = "apple", target_os = "freebsd", target_os = "netbsd"))]
+fn format_int(bytes: &[u8], signed: bool) -> Option<String> {
+    Some(match (bytes.len(), signed) {
+        (1, true) => (bytes[0] as i8).to_string(),
+        (1, false) => bytes[0].to_string(),
+        (2, true) => i16::from_ne_bytes([bytes[0], bytes[1]]).to_string(),
+        (2, false) => u16::from_ne_bytes([bytes[0], bytes[1]]).to_string(),
+        (4, true) => i32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]).to_string(),
+        (4, false) => u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]).to_string(),
+        (8, _) => {
+            let mut b = [0u8; 8];
+            b.copy_from_slice(bytes);
+            if signed { i64::from_ne_bytes(b).to_string() } else { u64::from_ne_bytes(b).to_string() }
+        }
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    pub fn test_sysctl_read() {
+        assert_eq!(read("kernel.ostype").unwrap(), "Linux");
+        assert!(read_int("kernel.pid_max").unwrap() > 0);
+        assert_eq!(read_ints("kernel.printk").unwrap().len(), 4);
+        assert!(read_int("kernel.ostype").is_err());
+        assert!(read("no.such.parameter").is_err());
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    pub fn test_sysctl_list() {
+        let names = list("kernel.").unwrap();
+        assert!(names.iter().any(|name| name == "kernel.ostype"));
+        assert!(names.iter().all(|name| name.starts_with("kernel.")));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    pub fn test_sysctl_list_prefix() {
+        let names = list("kernel.os").unwrap();
+        assert!(names.iter().any(|name| name == "kernel.ostype"));
+        assert!(names.iter().all(|name| name.starts_with("kernel.os")));
+        assert!(list("fs.").unwrap().iter().all(|name| !name.starts_with("fs.binfmt_misc.")));
+        assert!(list("kernel.ostype.").unwrap().is_empty());
+        assert!(list("no.such.").unwrap().is_empty());
+    }
+
+    #[test]
+    pub fn test_format_value() {
+        let int = ValueType::Int { size: 4, signed: true };
+        assert_eq!(format_value("vm.swappiness", &60i32.to_ne_bytes(), int).unwrap(), "60");
+        assert_eq!(format_value("hw.pagesize", &4096i32.to_ne_bytes(), int).unwrap(), "4096");
+        assert_eq!(format_value("x", &(-1i32).to_ne_bytes(), int).unwrap(), "-1");
+        let uint = ValueType::Int { size: 4, signed: false };
+        assert_eq!(format_value("x", &(-1i32).to_ne_bytes(), uint).unwrap(), "4294967295");
+        let quad = ValueType::Int { size: 8, signed: true };
+        assert_eq!(format_value("hw.memsize", &(8i64 << 30).to_ne_bytes(), quad).unwrap(),
+                   "8589934592");
+        let mut buf = Vec::new();
+        for value in &[1i32, 2, 0] {
+            buf.extend_from_slice(&value.to_ne_bytes());
+        }
+        assert_eq!(format_value("x", &buf, int).unwrap(), "1 2 0");
+        assert_eq!(format_value("x", &[1], ValueType::Int { size: 1, signed: false }).unwrap(),
+                   "1");
+        assert!(format_value("x", &[0; 6], int).is_err());
+        assert_eq!(format_value("kern.ostype", b"Darwin\0", ValueType::String).unwrap(),
+                   "Darwin");
+        assert!(format_value("vm.loadavg", &[0; 24], ValueType::Other).is_err());
+    }
+
+    #[test]
+    pub fn test_value_types() {
+        let long = std::mem::size_of::<libc::c_long>();
+        assert_eq!(oidfmt_type(2, "I"), ValueType::Int { size: 4, signed: true });
+        assert_eq!(oidfmt_type(0x8000_0002, "IU"), ValueType::Int { size: 4, signed: false });
+        assert_eq!(oidfmt_type(2, "LU"), ValueType::Int { size: long, signed: false });
+        assert_eq!(oidfmt_type(3, "A"), ValueType::String);
+        assert_eq!(oidfmt_type(4, "Q"), ValueType::Int { size: 8, signed: true });
+        assert_eq!(oidfmt_type(9, "QU"), ValueType::Int { size: 8, signed: false });
+        assert_eq!(oidfmt_type(5, "S,loadavg"), ValueType::Other);
+        assert_eq!(netbsd_type(0x0100_0002), ValueType::Int { size: 4, signed: true });
+        assert_eq!(netbsd_type(0x0008_0004), ValueType::Int { size: 8, signed: false });
+        assert_eq!(netbsd_type(6), ValueType::Int { size: 1, signed: false });
+        assert_eq!(netbsd_type(5), ValueType::Other);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    pub fn test_name_to_path() {
+        assert_eq!(name_to_path("vm.swappiness"), Path::new("/proc/sys/vm/swappiness"));
+        assert_eq!(name_to_path("net.ipv4.conf.eth0/1.forwarding"),
+                   Path::new("/proc/sys/net/ipv4/conf/eth0.1/forwarding"));
+        assert_eq!(name_to_path("net/ipv4/conf/eth0.1/forwarding"),
+                   Path::new("/proc/sys/net/ipv4/conf/eth0.1/forwarding"));
+    }
+}
@@ -23,6 +23,7 @@ fn main() {
         let t = boottime().unwrap();
         println!("boottime {} sec, {} usec", t.tv_sec, t.tv_usec);
     }
+    println!("uptime: {:?}, boot time: {:?}", uptime().unwrap(), boot_time().unwrap());
     #[cfg(target_os = "linux")]
     println!("/etc/os-release: {:?}", linux_os_release().unwrap());
 }
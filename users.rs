@@ -0,0 +1,151 @@
+#![cfg(target_os = "linux")]
+
+//! Login sessions from utmp and wtmp, see utmp(5).
+
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::os::raw::c_char;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use super::Error;
+
+const UTMP: &str = "/var/run/utmp";
+const WTMP: &str = "/var/log/wtmp";
+
+/// getutxent() iterates over global state, so only one thread may use it at a time.
+static UTMPX_LOCK: Mutex<()> = Mutex::new(());
+
+/// A user logged in to the system.
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub user: String,
+    /// Terminal, such as "pts/0" or "tty1".
+    pub tty: String,
+    /// Remote host for network logins, such as "10.0.0.5".
+    pub host: Option<String>,
+    pub login_time: SystemTime,
+    /// Process ID of the login process.
+    pub pid: u32,
+}
+
+/// A past or current login, from wtmp.
+#[derive(Debug)]
+pub struct LoginRecord {
+    pub session: Session,
+    /// `None` while the user is still logged in, or if the system went down
+    /// without recording the logout.
+    pub logout_time: Option<SystemTime>,
+}
+
+/// Get the users currently logged in, from utmp.
+pub fn users() -> Result<Vec<Session>, Error> {
+    let entries = read_utmpx(UTMP)?;
+    Ok(entries.into_iter()
+        .filter(|entry| entry.kind == libc::USER_PROCESS)
+        .map(|entry| entry.session)
+        .collect())
+}
+
+/// Get the logins recorded in /var/log/wtmp, most recent first, like last(1).
+///
+/// Logins still open when the system rebooted have no logout time.
+pub fn last_logins() -> Result<Vec<LoginRecord>, Error> {
+    let entries = read_utmpx(WTMP)?;
+    let mut logins: Vec<LoginRecord> = Vec::new();
+    // Index into `logins` of the open login on each tty.
+    let mut open: HashMap<String, usize> = HashMap::new();
+    for entry in entries {
+        match entry.kind {
+            libc::USER_PROCESS => {
+                open.insert(entry.session.tty.clone(), logins.len());
+                logins.push(LoginRecord { session: entry.session, logout_time: None });
+            }
+            libc::DEAD_PROCESS => {
+                if let Some(i) = open.remove(&entry.session.tty) {
+                    logins[i].logout_time = Some(entry.session.login_time);
+                }
+            }
+            libc::BOOT_TIME => open.clear(),
+            _ => {}
+        }
+    }
+    logins.reverse();
+    Ok(logins)
+}
+
+struct Entry {
+    kind: libc::c_short,
+    session: Session,
+}
+
+fn read_utmpx(path: &str) -> Result<Vec<Entry>, Error> {
+    let cpath = CString::new(path).map_err(|_| Error::Unknown)?;
+    let _guard = UTMPX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut entries = Vec::new();
+    unsafe {
+        if libc::utmpxname(cpath.as_ptr()) != 0 {
+            return Err(Error::Unknown);
+        }
+        libc::setutxent();
+        loop {
+            let ut = libc::getutxent();
+            if ut.is_null() {
+                break;
+            }
+            let ut = &*ut;
+            let secs = ut.ut_tv.tv_sec as u64;
+            let usecs = ut.ut_tv.tv_usec as u64;
+            let host = field(&ut.ut_host);
+            entries.push(Entry {
+                kind: ut.ut_type,
+                session: Session {
+                    user: field(&ut.ut_user),
+                    tty: field(&ut.ut_line),
+                    host: if host.is_empty() { None } else { Some(host) },
+                    login_time: SystemTime::UNIX_EPOCH
+                        + Duration::from_secs(secs)
+                        + Duration::from_micros(usecs),
+                    pid: ut.ut_pid as u32,
+                },
+            });
+        }
+        libc::endutxent();
+        if path != UTMP {
+            let default = CString::new(UTMP).map_err(|_| Error::Unknown)?;
+            libc::utmpxname(default.as_ptr());
+        }
+    }
+    Ok(entries)
+}
+
+/// Read a fixed size field, which is only NUL-terminated if shorter than the field.
+fn field(buf: &[c_char]) -> String {
+    let bytes: Vec<u8> = buf.iter().take_while(|&&c| c != 0).map(|&c| c as u8).collect();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_users() {
+        let users = users().unwrap();
+        println!("users(): {:?}", users);
+    }
+
+    #[test]
+    pub fn test_last_logins() {
+        let logins = last_logins().unwrap();
+        println!("last_logins(): {} records", logins.len());
+    }
+
+    #[test]
+    pub fn test_field() {
+        let buf = [b'p' as c_char, b't' as c_char, b's' as c_char, 0, b'x' as c_char];
+        assert_eq!(field(&buf), "pts");
+        let buf = [b'a' as c_char, b'b' as c_char];
+        assert_eq!(field(&buf), "ab");
+    }
+}
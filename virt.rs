@@ -0,0 +1,242 @@
+#![cfg(target_os = "linux")]
+
+//! Hypervisor detection, in the spirit of systemd-detect-virt.
+
+use std::fs::File;
+use std::io::Read;
+
+use super::{dmi_info, read_trimmed, DmiInfo, Error};
+
+/// The hypervisor the system runs under.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Virtualization {
+    /// Not virtualized.
+    BareMetal,
+    Kvm,
+    /// QEMU without KVM acceleration.
+    Qemu,
+    Vmware,
+    HyperV,
+    Xen,
+    VirtualBox,
+    Parallels,
+    Bhyve,
+    /// Amazon EC2 instances on the Nitro hypervisor.
+    AwsNitro,
+    /// Google Compute Engine.
+    Gce,
+    /// A hypervisor identifying itself with an unrecognized CPUID vendor string.
+    Other(String),
+    /// The CPU reports a hypervisor, but nothing tells which one.
+    Unknown,
+}
+
+impl Virtualization {
+    /// Whether the system runs under a hypervisor.
+    pub fn is_virtual(&self) -> bool {
+        *self != Virtualization::BareMetal
+    }
+}
+
+/// Detect whether the system runs on bare metal or under a hypervisor.
+///
+/// Looks at, in order, the CPUID hypervisor leaf refined by cloud vendors in DMI,
+/// other DMI strings, `/sys/hypervisor` and the `hypervisor` flag in `/proc/cpuinfo`.
+///
+/// Notice, this doesn't detect containers, which share the host kernel. See `container()`.
+pub fn virtualization() -> Result<Virtualization, Error> {
+    let dmi = dmi_info()?;
+    if let Some(virt) = detect(&dmi, &cpuid()) {
+        return Ok(virt);
+    }
+    if read_trimmed("/sys/hypervisor/type").ok().as_deref() == Some("xen") {
+        return Ok(Virtualization::Xen);
+    }
+    if cpuinfo_has_hypervisor_flag()? {
+        return Ok(Virtualization::Unknown);
+    }
+    Ok(Virtualization::BareMetal)
+}
+
+/// What the CPU tells about a hypervisor.
+enum Cpuid {
+    /// The hypervisor bit is set, with the vendor signature of leaf 0x40000000.
+    Hypervisor(String),
+    NoHypervisor,
+    /// Not an x86 CPU, so only DMI and the kernel can tell.
+    #[cfg_attr(any(target_arch = "x86", target_arch = "x86_64"), allow(dead_code))]
+    Unavailable,
+}
+
+fn detect(dmi: &DmiInfo, cpuid: &Cpuid) -> Option<Virtualization> {
+    match *cpuid {
+        Cpuid::Hypervisor(ref vendor) => {
+            Some(detect_cloud(dmi).unwrap_or_else(|| from_cpuid_vendor(vendor)))
+        }
+        Cpuid::NoHypervisor => detect_dmi(dmi),
+        // Bare-metal EC2 instances report the same vendor, with types like "c6g.metal".
+        Cpuid::Unavailable if ends_with(&dmi.product_name, ".metal") => detect_dmi(dmi),
+        Cpuid::Unavailable => detect_cloud(dmi).or_else(|| detect_dmi(dmi)),
+    }
+}
+
+/// Cloud instances whose DMI strings are more specific than the CPUID vendor,
+/// which is plain KVM for both.
+///
+/// Only meaningful under a hypervisor: bare-metal EC2 instances report "Amazon EC2"
+/// too, and physical Chromebooks report "Google".
+fn detect_cloud(dmi: &DmiInfo) -> Option<Virtualization> {
+    if starts_with(&dmi.sys_vendor, "Amazon EC2") || starts_with(&dmi.bios_vendor, "Amazon EC2") {
+        Some(Virtualization::AwsNitro)
+    } else if dmi.product_name.as_deref() == Some("Google Compute Engine") {
+        Some(Virtualization::Gce)
+    } else {
+        None
+    }
+}
+
+fn detect_dmi(dmi: &DmiInfo) -> Option<Virtualization> {
+    let vendors = [
+        ("KVM", Virtualization::Kvm),
+        ("QEMU", Virtualization::Qemu),
+        ("VMware", Virtualization::Vmware),
+        ("VMW", Virtualization::Vmware),
+        ("innotek GmbH", Virtualization::VirtualBox),
+        ("VirtualBox", Virtualization::VirtualBox),
+        ("Xen", Virtualization::Xen),
+        ("Parallels", Virtualization::Parallels),
+        ("BHYVE", Virtualization::Bhyve),
+    ];
+    let fields = [&dmi.product_name, &dmi.sys_vendor, &dmi.board_vendor, &dmi.bios_vendor];
+    for field in fields.iter().filter_map(|field| field.as_ref()) {
+        for &(prefix, ref virt) in vendors.iter() {
+            if field.starts_with(prefix) {
+                return Some(virt.clone());
+            }
+        }
+    }
+    // Hyper-V reports Microsoft as the vendor, which physical Surface devices do too.
+    if dmi.sys_vendor.as_deref() == Some("Microsoft Corporation")
+        && dmi.product_name.as_deref() == Some("Virtual Machine")
+    {
+        return Some(Virtualization::HyperV);
+    }
+    None
+}
+
+fn starts_with(field: &Option<String>, prefix: &str) -> bool {
+    match *field {
+        Some(ref value) => value.starts_with(prefix),
+        None => false,
+    }
+}
+
+fn ends_with(field: &Option<String>, suffix: &str) -> bool {
+    match *field {
+        Some(ref value) => value.ends_with(suffix),
+        None => false,
+    }
+}
+
+fn from_cpuid_vendor(vendor: &str) -> Virtualization {
+    match vendor.trim_end_matches('\0') {
+        "KVMKVMKVM" | "Linux KVM Hv" => Virtualization::Kvm,
+        "TCGTCGTCGTCG" => Virtualization::Qemu,
+        "VMwareVMware" => Virtualization::Vmware,
+        "Microsoft Hv" => Virtualization::HyperV,
+        "XenVMMXenVMM" => Virtualization::Xen,
+        "VBoxVBoxVBox" => Virtualization::VirtualBox,
+        " lrpepyh  vr" => Virtualization::Parallels,
+        "bhyve bhyve " => Virtualization::Bhyve,
+        other => Virtualization::Other(other.to_string()),
+    }
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[allow(unused_unsafe)] // __cpuid is only safe to call on newer compilers.
+fn cpuid() -> Cpuid {
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::__cpuid;
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::__cpuid;
+
+    // Bit 31 of ECX in leaf 1 is the hypervisor present bit.
+    let leaf1 = unsafe { __cpuid(1) };
+    if leaf1.ecx & (1 << 31) == 0 {
+        return Cpuid::NoHypervisor;
+    }
+    let leaf = unsafe { __cpuid(0x4000_0000) };
+    let mut signature = Vec::with_capacity(12);
+    for reg in &[leaf.ebx, leaf.ecx, leaf.edx] {
+        signature.extend_from_slice(&reg.to_le_bytes());
+    }
+    Cpuid::Hypervisor(String::from_utf8_lossy(&signature).into_owned())
+}
+
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+fn cpuid() -> Cpuid {
+    Cpuid::Unavailable
+}
+
+fn cpuinfo_has_hypervisor_flag() -> Result<bool, Error> {
+    let mut s = String::new();
+    File::open("/proc/cpuinfo")?.read_to_string(&mut s)?;
+    Ok(s.lines()
+        .filter(|line| line.starts_with("flags"))
+        .any(|line| line.split_whitespace().any(|flag| flag == "hypervisor")))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_virtualization() {
+        let virt = virtualization().unwrap();
+        println!("virtualization(): {:?}", virt);
+    }
+
+    #[test]
+    pub fn test_detect_dmi() {
+        let mut dmi = DmiInfo::default();
+        assert_eq!(detect_dmi(&dmi), None);
+        dmi.sys_vendor = Some("QEMU".to_string());
+        assert_eq!(detect_dmi(&dmi), Some(Virtualization::Qemu));
+        dmi.sys_vendor = Some("Microsoft Corporation".to_string());
+        dmi.product_name = Some("Virtual Machine".to_string());
+        assert_eq!(detect_dmi(&dmi), Some(Virtualization::HyperV));
+    }
+
+    #[test]
+    pub fn test_detect_cloud() {
+        let kvm = Cpuid::Hypervisor("KVMKVMKVM\0\0\0".to_string());
+        let mut dmi = DmiInfo {
+            sys_vendor: Some("Amazon EC2".to_string()),
+            product_name: Some("m5.large".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(detect(&dmi, &kvm), Some(Virtualization::AwsNitro));
+        assert_eq!(detect(&dmi, &Cpuid::Unavailable), Some(Virtualization::AwsNitro));
+        // Bare-metal instances.
+        dmi.product_name = Some("m5.metal".to_string());
+        assert_eq!(detect(&dmi, &Cpuid::NoHypervisor), None);
+        dmi.product_name = Some("c6g.metal".to_string());
+        assert_eq!(detect(&dmi, &Cpuid::Unavailable), None);
+
+        dmi.sys_vendor = Some("Google".to_string());
+        dmi.product_name = Some("Google Compute Engine".to_string());
+        assert_eq!(detect(&dmi, &kvm), Some(Virtualization::Gce));
+        // A physical Pixelbook, and a VM on one.
+        dmi.product_name = Some("Eve".to_string());
+        assert_eq!(detect(&dmi, &Cpuid::NoHypervisor), None);
+        assert_eq!(detect(&dmi, &kvm), Some(Virtualization::Kvm));
+    }
+
+    #[test]
+    pub fn test_from_cpuid_vendor() {
+        assert_eq!(from_cpuid_vendor("KVMKVMKVM\0\0\0"), Virtualization::Kvm);
+        assert_eq!(from_cpuid_vendor("Microsoft Hv"), Virtualization::HyperV);
+        assert_eq!(from_cpuid_vendor("NewVisorNewV"),
+                   Virtualization::Other("NewVisorNewV".to_string()));
+    }
+}